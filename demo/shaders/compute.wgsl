@@ -2,7 +2,7 @@
 
 @group(0)
 @binding(0)
-var dest: texture_storage_2d<r32float, write>;
+var dest: texture_storage_2d<rgba16float, write>;
 
 struct State {
     offset: vec2<f32>,
@@ -25,6 +25,10 @@ var<uniform> state: ObjectUniform;
 @binding(1)
 var r_color: texture_2d<f32>;
 
+@group(0)
+@binding(2)
+var r_sampler: sampler;
+
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var out: VertexOutput;
@@ -47,7 +51,6 @@ fn hsv2rgb(c: vec3<f32>) -> vec3<f32> {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let tex_coords = vec2<i32>(in.text_coords * vec2<f32>(textureDimensions(r_color)));
-    let color = textureLoad(r_color, tex_coords, 0).r;
+    let color = textureSample(r_color, r_sampler, in.text_coords).r;
     return vec4(hsv2rgb(vec3(color, 1.0, 1.0)), 1.0);
 }
\ No newline at end of file
@@ -4,8 +4,16 @@ use bytemuck::{Pod, Zeroable};
 use petra::{
     manager::{RenderManager, SurfaceError},
     render_pipeline::{FrontFace, PrimitiveTopology},
-    texture::FRAMEBUFFER,
-    wgpu::{Color, ShaderStages, StorageTextureAccess, TextureSampleType, TextureViewDimension},
+    texture::{Half, FRAMEBUFFER},
+    wgpu::{
+        Color,
+        FilterMode,
+        SamplerBindingType,
+        ShaderStages,
+        StorageTextureAccess,
+        TextureSampleType,
+        TextureViewDimension,
+    },
     Vertex,
 };
 use petra_math::{Quat, Vec2, Vec3};
@@ -129,17 +137,13 @@ fn main() {
 
     let compute_shader = manager.register_shader(include_str!("../shaders/compute.wgsl"), None);
     let compute_texture = manager
-        .texture_builder::<f32>(Some("Compute Storage Texture"))
+        .texture_builder::<Half<[u16; 4]>>(Some("Compute Storage Texture"))
         .size_2d(1024, 1024)
         .texture()
         .storage()
         .build();
 
-    let compute_buffer = manager
-        .buffer_builder::<ComputeUniform>(Some("Compute buffer"))
-        .uniform()
-        .copy_dst()
-        .build(1);
+    let compute_buffer = manager.create_uniform(Some("Compute buffer"), ComputeUniform::zeroed());
 
     let compute_bind_group = manager
         .bind_group_builder(Some("Compute Bind Group"))
@@ -167,13 +171,16 @@ fn main() {
     let triangle_vert_buffer = manager
         .buffer_builder::<ColorPosVertex>(Some("Triangle Vertex Buffer"))
         .vertex()
-        .build_init(build_triangle_vertecies(Vec3::ZERO, 1.0, 0.0).to_vec());
+        .build_init(build_triangle_vertecies(Vec3::ZERO, 1.0, 0.0));
 
-    let triangle_state_buffer = manager
-        .buffer_builder::<TriangleUniform>(Some("Rotation Buffer"))
-        .uniform()
-        .copy_dst()
-        .build(1);
+    let triangle_state_buffer =
+        manager.create_uniform(Some("Rotation Buffer"), TriangleUniform::default());
+
+    let compute_sampler = manager
+        .texture_sampler_builder(Some("Compute Texture Sampler"))
+        .mag_filter(FilterMode::Linear)
+        .min_filter(FilterMode::Linear)
+        .build();
 
     let bind_group = manager
         .bind_group_builder(Some("Triangle Bind Group"))
@@ -181,11 +188,17 @@ fn main() {
         .bind_texture(
             1,
             ShaderStages::FRAGMENT,
-            TextureSampleType::Float { filterable: false },
+            TextureSampleType::Float { filterable: true },
             TextureViewDimension::D2,
             false,
             compute_texture,
         )
+        .bind_sampler(
+            2,
+            ShaderStages::FRAGMENT,
+            SamplerBindingType::Filtering,
+            compute_sampler,
+        )
         .build();
 
     let triangle_shader = manager.register_shader(include_str!("../shaders/triangle.wgsl"), None);
@@ -203,12 +216,12 @@ fn main() {
     let quad_vert_buffer = manager
         .buffer_builder::<ColorPosTexVertex>(Some("Quad Vertex Buffer"))
         .vertex()
-        .build_init(quad_verts.to_vec());
+        .build_init(quad_verts);
 
     let quad_idx_buffer = manager
         .buffer_builder::<u16>(Some("Quad Index Buffer"))
         .index()
-        .build_init(quad_idx.to_vec());
+        .build_init(quad_idx);
 
     let quad_shader = manager.register_shader(include_str!("../shaders/quad.wgsl"), None);
     let quad_pipeline = manager
@@ -240,7 +253,7 @@ fn main() {
             window_id,
             ref event,
         } =>
-            if window_id == manager.window.id() {
+            if window_id == manager.window().id() {
                 match event {
                     WindowEvent::CloseRequested
                     | WindowEvent::KeyboardInput {
@@ -329,7 +342,7 @@ fn main() {
                 }
             },
         Event::RedrawRequested(window_id) =>
-            if window_id == manager.window.id() {
+            if window_id == manager.window().id() {
                 if spinning {
                     shape_state.rotation *= Quat::from_axis_angle(Vec3::Z, FRAC_PI_8 / 24.0);
                 }
@@ -337,14 +350,15 @@ fn main() {
                 manager.write_to_buffer(compute_buffer, &[fractal_state]);
 
 
-                match manager.render() {
+                match manager.render_simple() {
                     Ok(_) => {}
-                    Err(SurfaceError::OutOfMemory) | Err(SurfaceError::Lost) => manager.recreate(),
-                    Err(SurfaceError::Outdated) => *control_flow = ControlFlow::Exit,
+                    Err(SurfaceError::Lost) | Err(SurfaceError::OutOfMemory) =>
+                        *control_flow = ControlFlow::Exit,
+                    Err(SurfaceError::Outdated) => manager.recreate(),
                     Err(SurfaceError::Timeout) => println!("Surface timed out"),
                 }
             },
-        Event::MainEventsCleared => manager.window.request_redraw(),
+        Event::MainEventsCleared => manager.window().request_redraw(),
         _ => {}
     });
 }
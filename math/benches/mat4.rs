@@ -0,0 +1,99 @@
+//! Times `Mat4` multiplication against the old product built from rows and columns,
+//! and the `Mat4` and `Vec4` operations that have SSE versions
+//!
+//! Run with `cargo bench -p petra_math` for the scalar path and add `--features simd` to compare
+//! against the SSE path.
+
+use std::{hint::black_box, time::Instant};
+
+use petra_math::{Mat4, Vec4};
+
+const ITERATIONS: usize = 1_000_000;
+
+fn random_matrices(count: usize) -> Vec<Mat4> {
+    // xorshift32
+    let mut state = 0x2545_F491_u32;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32 * 20.0 - 10.0
+    };
+
+    (0 .. count)
+        .map(|_| {
+            Mat4::from_vector_rows(
+                Vec4::new(next(), next(), next(), next()),
+                Vec4::new(next(), next(), next(), next()),
+                Vec4::new(next(), next(), next(), next()),
+                Vec4::new(next(), next(), next(), next()),
+            )
+        })
+        .collect()
+}
+
+/// How `Mat4 * Mat4` used to be done, one row and column `Vec4` per element
+fn row_column_mul(a: Mat4, b: Mat4) -> Mat4 {
+    let row = |i| {
+        Vec4::new(
+            a.nth_row(i).dot(b.nth_column(0)),
+            a.nth_row(i).dot(b.nth_column(1)),
+            a.nth_row(i).dot(b.nth_column(2)),
+            a.nth_row(i).dot(b.nth_column(3)),
+        )
+    };
+    Mat4::from_vector_rows(row(0), row(1), row(2), row(3))
+}
+
+fn bench(name: &str, mut f: impl FnMut(usize)) {
+    // Warm up the caches before timing
+    for i in 0 .. ITERATIONS / 10 {
+        f(i);
+    }
+
+    let start = Instant::now();
+    for i in 0 .. ITERATIONS {
+        f(i);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:<24} {:>8.2} ns/iter",
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    let matrices = random_matrices(1024);
+    let pair = |i: usize| (matrices[i % 1024], matrices[(i + 1) % 1024]);
+
+    println!(
+        "simd feature: {}",
+        cfg!(all(feature = "simd", target_arch = "x86_64"))
+    );
+
+    bench("Mat4 * Mat4 (rows/cols)", |i| {
+        let (a, b) = pair(i);
+        black_box(row_column_mul(black_box(a), black_box(b)));
+    });
+    bench("Mat4 * Mat4", |i| {
+        let (a, b) = pair(i);
+        black_box(black_box(a) * black_box(b));
+    });
+    bench("Mat4 * f32", |i| {
+        let (a, b) = pair(i);
+        black_box(black_box(a) * black_box(b[0][0]));
+    });
+    bench("Mat4 * Vec4", |i| {
+        let (a, b) = pair(i);
+        black_box(black_box(a) * black_box(b.nth_row(0)));
+    });
+    bench("Vec4 + Vec4", |i| {
+        let (a, b) = pair(i);
+        black_box(black_box(a.nth_row(0)) + black_box(b.nth_row(0)));
+    });
+    bench("Vec4 * f32", |i| {
+        let (a, b) = pair(i);
+        black_box(black_box(a.nth_row(0)) * black_box(b[0][0]));
+    });
+}
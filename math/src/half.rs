@@ -0,0 +1,108 @@
+/// Converts an f32 to the bits of the nearest half precision float
+///
+/// Rounds to nearest with ties to even like the gpu does, values too large for a half become
+/// infinity and values too small become 0 or a subnormal.<br>
+/// This is needed to upload data to `Float16` vertex attributes and `Half` textures.
+pub fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    // Infinity and NaN, keeping NaNs as NaNs even if the top mantissa bits are 0
+    if exponent == 0xFF {
+        let nan = if mantissa != 0 {
+            0x200 | (mantissa >> 13) as u16
+        } else {
+            0
+        };
+        return sign | 0x7C00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+
+    if half_exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+
+    if half_exponent <= 0 {
+        // Too small to round up to the smallest subnormal
+        if half_exponent < -10 {
+            return sign;
+        }
+
+        // Subnormal, shift the mantissa with its implicit leading 1 into place
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let half = mantissa >> shift;
+        let halfway = 1 << (shift - 1);
+        let rest = mantissa & ((halfway << 1) - 1);
+
+        let round = rest > halfway || (rest == halfway && half & 1 == 1);
+        return sign | (half + round as u32) as u16;
+    }
+
+    let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let rest = mantissa & 0x1FFF;
+
+    // Rounding up can carry into the exponent, which correctly rounds up to infinity
+    let round = rest > 0x1000 || (rest == 0x1000 && half & 1 == 1);
+    sign | (half + round as u32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vec4;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(f32_to_f16_bits(1.0), 0x3C00);
+        assert_eq!(f32_to_f16_bits(-2.0), 0xC000);
+        assert_eq!(f32_to_f16_bits(0.5), 0x3800);
+        assert_eq!(f32_to_f16_bits(0.1), 0x2E66);
+        assert_eq!(f32_to_f16_bits(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16_bits(0.0), 0x0000);
+        assert_eq!(f32_to_f16_bits(-0.0), 0x8000);
+    }
+
+    #[test]
+    fn overflow_and_special_values() {
+        // Halfway between the largest half and the next power of two rounds up to infinity
+        assert_eq!(f32_to_f16_bits(65520.0), 0x7C00);
+        assert_eq!(f32_to_f16_bits(1e6), 0x7C00);
+        assert_eq!(f32_to_f16_bits(f32::INFINITY), 0x7C00);
+        assert_eq!(f32_to_f16_bits(f32::NEG_INFINITY), 0xFC00);
+        assert_eq!(f32_to_f16_bits(f32::NAN), 0x7E00);
+        // A NaN with only low mantissa bits set still has to be a NaN
+        assert_eq!(f32_to_f16_bits(f32::from_bits(0x7F80_0001)) & 0x3FF, 0x200);
+    }
+
+    #[test]
+    fn subnormals() {
+        assert_eq!(f32_to_f16_bits(2f32.powi(-14)), 0x0400);
+        assert_eq!(f32_to_f16_bits(2f32.powi(-14) - 2f32.powi(-24)), 0x03FF);
+        assert_eq!(f32_to_f16_bits(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16_bits(-2f32.powi(-24)), 0x8001);
+        // Exactly halfway to the smallest subnormal rounds to even, which is 0
+        assert_eq!(f32_to_f16_bits(2f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_f16_bits(2f32.powi(-30)), 0x0000);
+    }
+
+    #[test]
+    fn rounds_ties_to_even() {
+        assert_eq!(f32_to_f16_bits(1.0 + 2f32.powi(-11)), 0x3C00);
+        assert_eq!(f32_to_f16_bits(1.0 + 3.0 * 2f32.powi(-11)), 0x3C02);
+        assert_eq!(
+            f32_to_f16_bits(1.0 + 2f32.powi(-11) + 2f32.powi(-20)),
+            0x3C01
+        );
+    }
+
+    #[test]
+    fn vec4_to_f16x4() {
+        assert_eq!(Vec4::new(1.0, -2.0, 0.5, 0.0).to_f16x4(), [
+            0x3C00, 0xC000, 0x3800, 0x0000
+        ]);
+    }
+}
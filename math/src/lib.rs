@@ -1,7 +1,11 @@
+mod half;
 mod mat;
 mod quat;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd;
 mod vec;
 
+pub use half::*;
 pub use mat::*;
 pub use quat::*;
 pub use vec::*;
@@ -5,12 +5,34 @@ use std::{
 
 use bytemuck::{Zeroable, Pod};
 
-use crate::{Vec3, Vec4};
-
+use crate::{Vec2, Vec3, Vec4};
+
+/// A 4x4 matrix stored as 4 rows, with the translation in the last row
+///
+/// Vectors are treated as rows and transformed as `v * M`, so transforms are combined left to
+/// right: `model * view * projection` applies the model transform first.<br>
+/// In memory this is exactly a column major matrix that transforms column vectors, which is how
+/// WGSL's `mat4x4<f32>` works, so the shader uses it as `projection * view * model * position`.
+/// Use [`Mat4::for_shader`] to put one in a uniform.
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 #[repr(transparent)]
 pub struct Mat4([[f32; 4]; 4]);
 
+/// A [`Mat4`] laid out the way WGSL's `mat4x4<f32>` expects
+///
+/// Use this instead of [`Mat4`] in structs written to uniform and storage buffers.
+/// The only way to make one is [`Mat4::for_shader`], so a matrix can't be transposed twice
+/// or not at all on its way to the gpu.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct GpuMat4([[f32; 4]; 4]);
+
+impl From<Mat4> for GpuMat4 {
+    fn from(value: Mat4) -> Self {
+        value.for_shader()
+    }
+}
+
 impl Default for Mat4 {
     fn default() -> Self {
         Mat4::IDENTITY
@@ -59,6 +81,25 @@ impl Mat4 {
         ])
     }
 
+    /// Converts to the layout WGSL expects
+    ///
+    /// `Mat4`'s rows are already WGSL's columns so this doesn't need to move anything.
+    pub fn for_shader(&self) -> GpuMat4 {
+        GpuMat4(self.0)
+    }
+
+    /// The elements one row after another, this is the layout WGSL expects
+    pub fn to_rows_array(&self) -> [f32; 16] {
+        bytemuck::cast(self.0)
+    }
+
+    /// The elements one column after another, the transpose of what WGSL expects
+    ///
+    /// Only needed for apis that take column major matrices transforming row vectors.
+    pub fn to_cols_array(&self) -> [f32; 16] {
+        self.transpose().to_rows_array()
+    }
+
     fn mat2_det(x0y0: f32, x1y0: f32, x0y1: f32, x1y1: f32) -> f32 {
         x0y0 * x1y1 - x1y0 * x0y1
     }
@@ -69,31 +110,129 @@ impl Mat4 {
             + mat3[0][2] * Self::mat2_det(mat3[1][0], mat3[1][1], mat3[2][0], mat3[2][1])
     }
 
+    /// The determinant of the 3x3 left after removing `row` and `col`
+    fn minor(&self, row: usize, col: usize) -> f32 {
+        let mut mat3 = [[0.0; 3]; 3];
+        for (i, r) in (0 .. 4).filter(|r| *r != row).enumerate() {
+            for (j, c) in (0 .. 4).filter(|c| *c != col).enumerate() {
+                mat3[i][j] = self[r][c];
+            }
+        }
+        Self::mat3_det(mat3)
+    }
+
+    /// Expands along the first row, alternating the sign of each minor
     pub fn det(&self) -> f32 {
-        self[0][0]
-            * Self::mat3_det([
-                [self[1][1], self[2][1], self[3][1]],
-                [self[1][2], self[2][2], self[3][2]],
-                [self[1][3], self[2][3], self[3][3]],
-            ])
-            - self[0][1]
-                * Self::mat3_det([
-                    [self[0][1], self[2][1], self[3][1]],
-                    [self[0][2], self[2][2], self[3][2]],
-                    [self[0][3], self[2][3], self[3][3]],
-                ])
-            + self[0][2]
-                * Self::mat3_det([
-                    [self[0][1], self[1][2], self[3][1]],
-                    [self[0][1], self[1][2], self[3][2]],
-                    [self[0][1], self[1][2], self[3][3]],
-                ])
-            - self[0][3]
-                * Self::mat3_det([
-                    [self[0][1], self[1][1], self[2][1]],
-                    [self[0][2], self[1][2], self[2][2]],
-                    [self[0][3], self[1][3], self[2][3]],
-                ])
+        (0 .. 4)
+            .map(|col| {
+                let sign = if col % 2 == 0 { 1.0 } else { -1.0 };
+                sign * self[0][col] * self.minor(0, col)
+            })
+            .sum()
+    }
+
+    /// The inverse of the matrix, or `None` if the determinant is 0 or the inverse is too large
+    /// for an f32
+    ///
+    /// Useful for going from view space back to world space, like when picking.<br>
+    /// Matrices with tiny but non-zero determinants, like small uniform scales, still invert.
+    pub fn inverse(&self) -> Option<Mat4> {
+        // The inverse is the transpose of the cofactor matrix divided by the determinant
+        let mut cofactors = [[0.0; 4]; 4];
+        for (row, cofactor_row) in cofactors.iter_mut().enumerate() {
+            for (col, cofactor) in cofactor_row.iter_mut().enumerate() {
+                let sign = if (row + col) % 2 == 0 { 1.0 } else { -1.0 };
+                *cofactor = sign * self.minor(row, col);
+            }
+        }
+
+        let det: f32 = (0 .. 4).map(|col| self[0][col] * cofactors[0][col]).sum();
+        if det == 0.0 {
+            return None;
+        }
+
+        let inverse = Mat4(cofactors.map(|row| row.map(|v| v / det))).transpose();
+        inverse.is_finite().then_some(inverse)
+    }
+
+    /// Returns true if the determinant is further than `epsilon` from 0
+    pub fn is_invertible(&self, epsilon: f32) -> bool {
+        self.det().abs() > epsilon
+    }
+
+    /// The matrix to transform normals by so they stay perpendicular to the surface
+    ///
+    /// This is the inverse transpose of the upper left 3x3, with no translation.<br>
+    /// For transforms with only rotation and translation it's the same as the rotation part,
+    /// it only differs when there's non-uniform scale.<br>
+    /// Returns `None` if the 3x3 can't be inverted, like when a scale is 0,
+    /// using the same rule as [`Mat4::inverse`].
+    pub fn normal_matrix(&self) -> Option<Mat4> {
+        let m = self;
+
+        // The inverse transpose is the cofactor matrix divided by the determinant
+        let cofactors = [
+            [
+                m[1][1] * m[2][2] - m[1][2] * m[2][1],
+                m[1][2] * m[2][0] - m[1][0] * m[2][2],
+                m[1][0] * m[2][1] - m[1][1] * m[2][0],
+            ],
+            [
+                m[0][2] * m[2][1] - m[0][1] * m[2][2],
+                m[0][0] * m[2][2] - m[0][2] * m[2][0],
+                m[0][1] * m[2][0] - m[0][0] * m[2][1],
+            ],
+            [
+                m[0][1] * m[1][2] - m[0][2] * m[1][1],
+                m[0][2] * m[1][0] - m[0][0] * m[1][2],
+                m[0][0] * m[1][1] - m[0][1] * m[1][0],
+            ],
+        ];
+        let det = m[0][0] * cofactors[0][0] + m[0][1] * cofactors[0][1] + m[0][2] * cofactors[0][2];
+
+        if det == 0.0 {
+            return None;
+        }
+
+        let [c0, c1, c2] = cofactors.map(|row| row.map(|v| v / det));
+        let normal_matrix = Mat4([
+            [c0[0], c0[1], c0[2], 0.0],
+            [c1[0], c1[1], c1[2], 0.0],
+            [c2[0], c2[1], c2[2], 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        normal_matrix.is_finite().then_some(normal_matrix)
+    }
+
+    /// Re-orthonormalizes the rotation part of the matrix with Gram-Schmidt, keeping the translation
+    ///
+    /// Useful for cleaning up the error that builds up in a transform rotated every frame.<br>
+    /// This removes any scale, the x axis keeps its direction and the other axes are made
+    /// perpendicular to it.
+    pub fn orthonormalize(&self) -> Mat4 {
+        // With row vectors the axes are the first three rows
+        let x = Vec3::new(self[0][0], self[0][1], self[0][2]).normalize();
+        let y = Vec3::new(self[1][0], self[1][1], self[1][2]);
+        let y = (y - x * x.dot(y)).normalize();
+        let z = Vec3::new(self[2][0], self[2][1], self[2][2]);
+        let z = (z - x * x.dot(z) - y * y.dot(z)).normalize();
+
+        Mat4([
+            [x.x(), x.y(), x.z(), self[0][3]],
+            [y.x(), y.y(), y.z(), self[1][3]],
+            [z.x(), z.y(), z.z(), self[2][3]],
+            self[3],
+        ])
+    }
+
+    /// Returns true if every element is neither infinite nor NaN
+    pub fn is_finite(&self) -> bool {
+        self.0.iter().flatten().all(|v| v.is_finite())
+    }
+
+    /// Returns true if any element is NaN
+    pub fn is_nan(&self) -> bool {
+        self.0.iter().flatten().any(|v| v.is_nan())
     }
 
     pub fn transpose(&self) -> Mat4 {
@@ -214,6 +353,31 @@ impl Mat4 {
         ])
     }
 
+    /// A perspective projection that maps the near plane to a depth of 1 and the far plane to 0
+    ///
+    /// Floats have much more precision near 0 so flipping the depth range spreads it out
+    /// far more evenly across the scene, which helps with z-fighting in large scenes.<br>
+    /// This outputs wgpu clip space directly, so it shouldn't be combined with [`Mat4::OPENGL_TO_WGPU`].
+    ///
+    /// To use it the depth attachment needs to be cleared to `0.0` instead of `1.0`
+    /// and the pipeline's depth compare needs to be `CompareFunction::Greater` instead of `Less`.
+    pub fn perspective_projection_reverse_z(
+        fov_radians: f32,
+        aspect_ratio: f32,
+        near_clip: f32,
+        far_clip: f32,
+    ) -> Mat4 {
+        let focal_length = 1.0 / f32::tan(fov_radians * 0.5);
+        let depth_range = 1.0 / (far_clip - near_clip);
+
+        Mat4([
+            [focal_length / aspect_ratio, 0.0, 0.0, 0.0],
+            [0.0, focal_length, 0.0, 0.0],
+            [0.0, 0.0, near_clip * depth_range, -1.0],
+            [0.0, 0.0, near_clip * far_clip * depth_range, 0.0],
+        ])
+    }
+
     pub fn look_at(pos: Vec3, target: Vec3, up: Vec3) -> Mat4 {
         let z_axis = (target - pos).normalize();
 
@@ -228,6 +392,26 @@ impl Mat4 {
         ])
     }
 
+    /// Turns a pixel on the screen into a world space ray, returning its origin and direction
+    ///
+    /// `self` should be the [`inverse`](Self::inverse) of the view projection matrix, pixels start
+    /// at the top left of a surface of `surface_size` pixels.<br>
+    /// The ray starts on the near plane and points towards the far plane, this assumes wgpu's
+    /// depth range where the near plane is at a depth of 0, so it doesn't work with reverse z.
+    pub fn unproject(&self, pixel: Vec2, surface_size: Vec2) -> (Vec3, Vec3) {
+        let ndc = pixel.pixel_to_ndc(surface_size);
+
+        let unproject_depth = |depth: f32| {
+            let point = *self * Vec4::from_xy(ndc, depth, 1.0);
+            Vec3::new(point.x(), point.y(), point.z()) / point.w()
+        };
+
+        let near = unproject_depth(0.0);
+        let far = unproject_depth(1.0);
+
+        (near, (far - near).normalize())
+    }
+
     pub fn forward(&self) -> Vec3 {
         Vec3::new(-self[0][2], -self[1][2], -self[2][2]).normalize()
     }
@@ -256,33 +440,26 @@ impl Mat4 {
 impl Mul for Mat4 {
     type Output = Mat4;
 
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
     fn mul(self, rhs: Self) -> Self::Output {
-        Mat4([
-            [
-                self.nth_row(0).dot(rhs.nth_column(0)),
-                self.nth_row(0).dot(rhs.nth_column(1)),
-                self.nth_row(0).dot(rhs.nth_column(2)),
-                self.nth_row(0).dot(rhs.nth_column(3)),
-            ],
-            [
-                self.nth_row(1).dot(rhs.nth_column(0)),
-                self.nth_row(1).dot(rhs.nth_column(1)),
-                self.nth_row(1).dot(rhs.nth_column(2)),
-                self.nth_row(1).dot(rhs.nth_column(3)),
-            ],
-            [
-                self.nth_row(2).dot(rhs.nth_column(0)),
-                self.nth_row(2).dot(rhs.nth_column(1)),
-                self.nth_row(2).dot(rhs.nth_column(2)),
-                self.nth_row(2).dot(rhs.nth_column(3)),
-            ],
-            [
-                self.nth_row(3).dot(rhs.nth_column(0)),
-                self.nth_row(3).dot(rhs.nth_column(1)),
-                self.nth_row(3).dot(rhs.nth_column(2)),
-                self.nth_row(3).dot(rhs.nth_column(3)),
-            ],
-        ])
+        // Work on the raw arrays so we don't build a row and column Vec4 for every entry
+        let mut out = [[0.0; 4]; 4];
+
+        for (row, out_row) in out.iter_mut().enumerate() {
+            for (col, val) in out_row.iter_mut().enumerate() {
+                *val = self[row][0] * rhs[0][col]
+                    + self[row][1] * rhs[1][col]
+                    + self[row][2] * rhs[2][col]
+                    + self[row][3] * rhs[3][col];
+            }
+        }
+
+        Mat4(out)
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Mat4(crate::simd::mat4_mul(&self.0, &rhs.0))
     }
 }
 
@@ -295,6 +472,7 @@ impl MulAssign<Mat4> for Mat4 {
 impl Mul<f32> for Mat4 {
     type Output = Mat4;
 
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
     fn mul(self, rhs: f32) -> Self::Output {
         Mat4::from_vector_rows(
             self.nth_row(0) * rhs,
@@ -303,6 +481,31 @@ impl Mul<f32> for Mat4 {
             self.nth_row(3) * rhs,
         )
     }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn mul(self, rhs: f32) -> Self::Output {
+        Mat4(crate::simd::mat4_scale(&self.0, rhs))
+    }
+}
+
+/// Transforms `rhs` the same way `matrix * vector` does in WGSL
+///
+/// With the row vector convention `Mat4` uses this is really `rhs * self`.
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    fn mul(self, rhs: Vec4) -> Self::Output {
+        Vec4::from_array(self[0]) * rhs.x()
+            + Vec4::from_array(self[1]) * rhs.y()
+            + Vec4::from_array(self[2]) * rhs.z()
+            + Vec4::from_array(self[3]) * rhs.w()
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn mul(self, rhs: Vec4) -> Self::Output {
+        Vec4::from_array(crate::simd::mat4_vec4(&self.0, rhs.to_array()))
+    }
 }
 
 impl MulAssign<f32> for Mat4 {
@@ -324,3 +527,273 @@ impl IndexMut<usize> for Mat4 {
         &mut self.0[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    /// The same matrices every run, with elements in [-10, 10]
+    fn random_matrices(count: usize) -> Vec<Mat4> {
+        // xorshift32
+        let mut state = 0x2545_F491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 20.0 - 10.0
+        };
+
+        (0 .. count)
+            .map(|_| Mat4(std::array::from_fn(|_| std::array::from_fn(|_| next()))))
+            .collect()
+    }
+
+    #[test]
+    fn mul_matches_row_column_dot_products() {
+        for pair in random_matrices(256).chunks_exact(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let expected: [[f32; 4]; 4] = std::array::from_fn(|row| {
+                std::array::from_fn(|col| a.nth_row(row).dot(b.nth_column(col)))
+            });
+
+            // Both sum the products in the same order so they should match exactly
+            assert_eq!((a * b).0, expected);
+        }
+    }
+
+    // Runs against both the scalar and SSE versions, which sum in the same order without fused
+    // multiply-add so there's no tolerance
+    #[test]
+    fn mul_vec4_matches_scalar() {
+        let matrices = random_matrices(128);
+        for (m, v) in matrices.iter().zip(matrices.iter().skip(1)) {
+            let v = v.nth_row(0);
+            let expected: [f32; 4] = std::array::from_fn(|col| {
+                m[0][col] * v.x() + m[1][col] * v.y() + m[2][col] * v.z() + m[3][col] * v.w()
+            });
+
+            assert_eq!((*m * v).to_array(), expected);
+        }
+    }
+
+    #[test]
+    fn mul_f32_matches_scalar() {
+        for m in random_matrices(128) {
+            let s = m[1][2];
+            assert_eq!((m * s).0, m.0.map(|row| row.map(|v| v * s)));
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 1e-5, "{a} isn't close to {b}");
+    }
+
+    fn assert_mat_close(a: Mat4, b: Mat4) {
+        for (a, b) in a.0.iter().flatten().zip(b.0.iter().flatten()) {
+            assert_close(*a, *b);
+        }
+    }
+
+    /// The depth a point `distance` in front of the camera ends up at
+    fn depth(projection: Mat4, distance: f32) -> f32 {
+        let clip = projection * Vec4::new(0.0, 0.0, -distance, 1.0);
+        clip.z() / clip.w()
+    }
+
+    #[test]
+    fn reverse_z_maps_near_to_1_and_far_to_0() {
+        let projection = Mat4::perspective_projection_reverse_z(1.0, 16.0 / 9.0, 0.1, 100.0);
+
+        assert_close(depth(projection, 0.1), 1.0);
+        assert_close(depth(projection, 100.0), 0.0);
+        assert!(depth(projection, 1.0) > depth(projection, 10.0));
+    }
+
+    #[test]
+    fn orthonormalize_removes_scale_from_rows() {
+        let rotation = Mat4::rotation_eular_z(FRAC_PI_4);
+        let m = (Mat4::scale(Vec3::new(2.0, 1.0, 1.0)) * rotation).orthonormalize();
+
+        // Still rotated by 45 degrees, orthonormalizing columns would give 26.57
+        assert_close(m[0][1].atan2(m[0][0]).abs(), FRAC_PI_4);
+        assert_mat_close(m, rotation);
+    }
+
+    #[test]
+    fn orthonormalize_fixes_drift_and_keeps_translation() {
+        let mut m =
+            Mat4::roation_eular_xyz(0.3, -1.2, 2.0) * Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        m[0][1] += 0.01;
+        m[1][2] -= 0.02;
+        m[2][0] += 0.015;
+        let fixed = m.orthonormalize();
+
+        for i in 0 .. 3 {
+            let row = fixed.nth_row(i).xyz();
+            assert_close(row.magnitude(), 1.0);
+            for j in i + 1 .. 3 {
+                assert_close(row.dot(fixed.nth_row(j).xyz()), 0.0);
+            }
+        }
+        assert_eq!(fixed[3], m[3]);
+    }
+
+    #[test]
+    fn is_invertible_checks_det() {
+        assert!(Mat4::roation_eular_xyz(0.3, -1.2, 2.0).is_invertible(1e-6));
+        assert!(!Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).is_invertible(1e-6));
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular() {
+        let model = Mat4::scale(Vec3::new(1.0, 2.0, 1.0))
+            * Mat4::rotation_eular_y(0.4)
+            * Mat4::translation(Vec3::new(5.0, 0.0, 1.0));
+        let normal = Vec4::new(1.0, 1.0, 0.0, 0.0).normalize();
+        let tangent = Vec4::new(1.0, -1.0, 0.0, 0.0);
+
+        let tangent = model * tangent;
+        let transformed = model.normal_matrix().unwrap() * normal;
+        assert_close(transformed.dot(tangent), 0.0);
+        // Transforming the normal like a position doesn't work with non-uniform scale
+        assert!((model * normal).dot(tangent).abs() > 0.1);
+    }
+
+    #[test]
+    fn normal_matrix_of_rotation_is_rotation() {
+        let rotation = Mat4::roation_eular_xyz(0.3, -1.2, 2.0);
+        let normal_matrix = (rotation * Mat4::translation(Vec3::new(1.0, 2.0, 3.0)))
+            .normal_matrix()
+            .unwrap();
+
+        assert_mat_close(normal_matrix, rotation);
+        assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).normal_matrix().is_none());
+    }
+
+    #[test]
+    fn unproject_center_looks_at_target() {
+        let view_projection = Mat4::look_at(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y)
+            * Mat4::perspective_projection(1.0, 4.0 / 3.0, 0.1, 100.0)
+            * Mat4::OPENGL_TO_WGPU;
+        let size = Vec2::new(800.0, 600.0);
+        let inverse = view_projection.inverse().unwrap();
+
+        let (origin, direction) = inverse.unproject(size / 2.0, size);
+        assert!((origin - Vec3::new(0.0, 0.0, 4.9)).magnitude() < 1e-3, "{origin}");
+        assert!((direction - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-3, "{direction}");
+
+        // Points along the ray from any pixel project back onto that pixel
+        let pixel = Vec2::new(100.0, 500.0);
+        let (origin, direction) = inverse.unproject(pixel, size);
+        let clip = view_projection * Vec4::from_xyz(origin + direction * 20.0, 1.0);
+        let projected = Vec2::new(clip.x(), clip.y()) / clip.w();
+        assert!((projected.ndc_to_pixel(size) - pixel).magnitude() < 1e-2);
+    }
+
+    #[test]
+    fn rows_array_is_wgsl_layout() {
+        let m = Mat4::rotation_eular_x(0.7) * Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        let v = [4.0, -1.0, 2.0, 1.0];
+
+        // WGSL stores each column contiguously and `matrix * vector` sums the columns
+        let wgsl = m.to_rows_array();
+        let wgsl_mul: [f32; 4] =
+            std::array::from_fn(|row| (0 .. 4).map(|col| wgsl[col * 4 + row] * v[col]).sum());
+
+        assert_eq!(wgsl[12 .. 15], [1.0, 2.0, 3.0]);
+        assert_eq!(bytemuck::cast::<GpuMat4, [f32; 16]>(m.for_shader()), wgsl);
+        for (a, b) in wgsl_mul.iter().zip((m * Vec4::from_array(v)).to_array()) {
+            assert_close(*a, b);
+        }
+    }
+
+    #[test]
+    fn cols_array_is_transposed() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        let cols = m.to_cols_array();
+
+        assert_eq!([cols[3], cols[7], cols[11]], [1.0, 2.0, 3.0]);
+        assert_eq!(cols, m.transpose().to_rows_array());
+    }
+
+    #[test]
+    fn inverse_undoes_matrix() {
+        for m in random_matrices(64) {
+            let product = m * m.inverse().unwrap();
+            for (a, b) in product.0.iter().flatten().zip(Mat4::IDENTITY.0.iter().flatten()) {
+                assert!((a - b).abs() < 1e-3, "{product}");
+            }
+        }
+
+        assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn small_scales_invert() {
+        // The determinant is 1e-9, well below f32::EPSILON
+        let m = Mat4::scale(Vec3::fill(0.001));
+
+        assert_mat_close(m.inverse().unwrap() * m, Mat4::IDENTITY);
+        assert!(m.normal_matrix().is_some());
+    }
+
+    #[test]
+    fn overflowing_inverse_is_none() {
+        // A subnormal scale has a non-zero determinant but its inverse is infinite
+        let m = Mat4::scale(Vec3::new(1e-39, 1.0, 1.0));
+
+        assert_ne!(m.det(), 0.0);
+        assert!(m.inverse().is_none());
+        assert!(m.normal_matrix().is_none());
+    }
+
+    #[test]
+    fn det_of_known_matrices() {
+        assert_eq!(Mat4::IDENTITY.det(), 1.0);
+        assert_eq!(Mat4::scale(Vec3::new(2.0, 3.0, 4.0)).det(), 24.0);
+        assert_eq!(
+            Mat4([
+                [1.0, 2.0, 3.0, 4.0],
+                [5.0, 6.0, 7.0, 8.0],
+                [2.0, 6.0, 4.0, 8.0],
+                [3.0, 1.0, 1.0, 2.0],
+            ])
+            .det(),
+            72.0
+        );
+        assert_eq!(
+            Mat4([
+                [2.0, 0.0, 1.0, 3.0],
+                [1.0, -1.0, 0.0, 2.0],
+                [0.0, 4.0, 1.0, -2.0],
+                [3.0, 1.0, -1.0, 1.0],
+            ])
+            .det(),
+            -6.0
+        );
+        // Swapping two rows flips the sign
+        assert_eq!(
+            Mat4([
+                [0.0, 1.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+            .det(),
+            -1.0
+        );
+    }
+
+    #[test]
+    fn det_is_multiplicative() {
+        for pair in random_matrices(64).chunks_exact(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let expected = a.det() * b.det();
+
+            assert!(((a * b).det() - expected).abs() <= expected.abs() * 1e-3);
+            assert!((a.transpose().det() - a.det()).abs() <= a.det().abs() * 1e-4);
+        }
+    }
+}
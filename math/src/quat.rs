@@ -4,7 +4,7 @@ use bytemuck::{Pod, Zeroable};
 
 use crate::{Mat4, Vec3, Vec4};
 
-#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 #[repr(transparent)]
 pub struct Quat(Vec4);
 
@@ -110,10 +110,64 @@ impl Quat {
         Quat::new(s * axis.x(), s * axis.y(), s * axis.z(), c)
     }
 
+    /// Rotates `vec` by this quaternion, the same as `q * vec * q.conjugate()` but cheaper
+    ///
+    /// Positive angles rotate counterclockwise when looking down the axis towards the origin,
+    /// so rotating [`Vec3::X`] by a quarter turn around [`Vec3::Z`] gives [`Vec3::Y`].
+    pub fn rotate_vec3(&self, vec: Vec3) -> Vec3 {
+        let normalized = self.normalize();
+        let imaginary = normalized.xyz();
+        let t = imaginary.cross(vec) * 2.0;
+
+        vec + t * normalized.w() + imaginary.cross(t)
+    }
+
+    /// The inverse of [`Quat::from_axis_angle`]
+    ///
+    /// The angle is between 0 and 2π. There is no axis for an angle of 0,
+    /// so the identity returns the x axis with an angle of 0.
+    pub fn to_axis_angle(&self) -> (Vec3, f32) {
+        let normalized = self.normalize();
+        let w = normalized.w().clamp(-1.0, 1.0);
+        let sin_half_angle = (1.0 - w * w).sqrt();
+
+        if sin_half_angle < 1e-6 {
+            (Vec3::X, 0.0)
+        } else {
+            (normalized.xyz() / sin_half_angle, 2.0 * w.acos())
+        }
+    }
+
+    /// The angle this rotates by, between 0 and 2π
+    pub fn angle(&self) -> f32 {
+        2.0 * self.normalize().w().clamp(-1.0, 1.0).acos()
+    }
+
+    /// The angle of the smallest rotation from this orientation to `other`, between 0 and π
+    pub fn angle_to(&self, other: Quat) -> f32 {
+        let dot = self.normalize().dot(other.normalize()).abs();
+        2.0 * dot.min(1.0).acos()
+    }
+
     fn lerp(from: Quat, to: Quat, t: f32) -> Quat {
         Quat::from_vec4(Vec4::lerp(from.0, to.0, t))
     }
 
+    /// Normalized linear interpolation, a cheaper [`slerp`](Self::slerp) that always takes the
+    /// shortest path
+    ///
+    /// The result is the same rotation as `slerp` at `t` 0, 0.5, and 1 but it moves faster in
+    /// the middle than at the ends, which is usually fine for things like blending animations.
+    pub fn nlerp(from: Quat, to: Quat, t: f32) -> Quat {
+        let to = if from.dot(to) < 0.0 {
+            Quat::from_vec4(-to.0)
+        } else {
+            to
+        };
+
+        Quat::lerp(from, to, t).normalize()
+    }
+
     pub fn slerp(from: Quat, to: Quat, t: f32) -> Quat {
         let norm_f = from.normalize();
         let mut norm_t = to.normalize();
@@ -179,3 +233,91 @@ impl Default for Quat {
         Quat::IDENTITY
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 1e-5, "{a} isn't close to {b}");
+    }
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        assert!((a - b).magnitude() <= 1e-5, "{a} isn't close to {b}");
+    }
+
+    #[test]
+    fn quats_cast_to_floats() {
+        let quats = [Quat::IDENTITY, Quat::new(1.0, 2.0, 3.0, 4.0)];
+
+        assert_eq!(std::mem::size_of::<Quat>(), 16);
+        assert_eq!(bytemuck::cast_slice::<Quat, f32>(&quats), [
+            0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 3.0, 4.0
+        ]);
+        assert_eq!(bytemuck::cast_slice::<f32, Quat>(&[1.0, 2.0, 3.0, 4.0]), [
+            Quat::new(1.0, 2.0, 3.0, 4.0)
+        ]);
+        assert_eq!(Quat::zeroed(), Quat::new(0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn axis_angle_round_trips() {
+        let axis = Vec3::new(1.0, -2.0, 0.5).normalize();
+
+        for angle in [0.1, FRAC_PI_2, 2.5, PI, 4.0] {
+            let (round_axis, round_angle) = Quat::from_axis_angle(axis, angle).to_axis_angle();
+            assert_vec_close(round_axis, axis);
+            assert_close(round_angle, angle);
+            assert_close(Quat::from_axis_angle(axis, angle).angle(), angle);
+        }
+    }
+
+    #[test]
+    fn identity_has_no_angle() {
+        assert_eq!(Quat::IDENTITY.to_axis_angle(), (Vec3::X, 0.0));
+        assert_eq!(Quat::IDENTITY.angle(), 0.0);
+    }
+
+    #[test]
+    fn angle_to_takes_shortest_rotation() {
+        let a = Quat::from_axis_angle(Vec3::Y, 0.5);
+        let b = Quat::from_axis_angle(Vec3::Y, 1.5);
+
+        assert_close(a.angle_to(b), 1.0);
+        assert_close(b.angle_to(a), 1.0);
+        assert_close(a.angle_to(a), 0.0);
+        // Negating a quaternion gives the same rotation
+        assert_close(a.angle_to(Quat::from_vec4(-*b)), 1.0);
+        // A rotation of 3π/2 one way is π/2 the other way
+        assert_close(
+            Quat::IDENTITY.angle_to(Quat::from_axis_angle(Vec3::Z, 3.0 * FRAC_PI_2)),
+            FRAC_PI_2,
+        );
+    }
+
+    #[test]
+    fn nlerp_takes_shortest_path() {
+        let from = Quat::from_axis_angle(Vec3::Z, 0.2);
+        // The same rotation as 0.6 radians but on the other side of the hypersphere
+        let to = Quat::from_vec4(-*Quat::from_axis_angle(Vec3::Z, 0.6));
+
+        let halfway = Quat::nlerp(from, to, 0.5);
+        assert_close(halfway.magnitude(), 1.0);
+        assert_close(halfway.angle_to(Quat::from_axis_angle(Vec3::Z, 0.4)), 0.0);
+        assert_close(Quat::nlerp(from, to, 0.0).angle_to(from), 0.0);
+        assert_close(Quat::nlerp(from, to, 1.0).angle_to(to), 0.0);
+    }
+
+    #[test]
+    fn nlerp_matches_slerp_at_midpoint() {
+        let from = Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0).normalize(), 0.3);
+        let to = Quat::from_axis_angle(Vec3::Y, 2.0);
+
+        assert_close(
+            Quat::nlerp(from, to, 0.5).angle_to(Quat::slerp(from, to, 0.5)),
+            0.0,
+        );
+    }
+}
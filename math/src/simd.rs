@@ -0,0 +1,97 @@
+//! SSE implementations of the hot [`Mat4`](crate::Mat4) and [`Vec4`](crate::Vec4) operations
+//!
+//! SSE is part of the x86_64 baseline so these don't need any runtime feature detection.<br>
+//! The operations are done in the same order as the scalar code and don't use fused multiply-add,
+//! so the results are bit for bit the same as the scalar path.
+
+use std::arch::x86_64::{
+    __m128,
+    _mm_add_ps,
+    _mm_div_ps,
+    _mm_loadu_ps,
+    _mm_mul_ps,
+    _mm_set1_ps,
+    _mm_storeu_ps,
+    _mm_sub_ps,
+    _mm_xor_ps,
+};
+
+#[inline(always)]
+fn load(row: &[f32; 4]) -> __m128 {
+    // SAFETY: `row` points to 4 contiguous f32s and loadu has no alignment requirement
+    unsafe { _mm_loadu_ps(row.as_ptr()) }
+}
+
+#[inline(always)]
+fn store(val: __m128) -> [f32; 4] {
+    let mut out = [0.0; 4];
+    // SAFETY: `out` has room for 4 f32s and storeu has no alignment requirement
+    unsafe { _mm_storeu_ps(out.as_mut_ptr(), val) };
+    out
+}
+
+pub(crate) fn mat4_mul(lhs: &[[f32; 4]; 4], rhs: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let rhs_rows = [load(&rhs[0]), load(&rhs[1]), load(&rhs[2]), load(&rhs[3])];
+    let mut out = [[0.0; 4]; 4];
+
+    for (out_row, lhs_row) in out.iter_mut().zip(lhs) {
+        // SAFETY: SSE is always available on x86_64
+        *out_row = store(unsafe {
+            let mut acc = _mm_mul_ps(_mm_set1_ps(lhs_row[0]), rhs_rows[0]);
+            acc = _mm_add_ps(acc, _mm_mul_ps(_mm_set1_ps(lhs_row[1]), rhs_rows[1]));
+            acc = _mm_add_ps(acc, _mm_mul_ps(_mm_set1_ps(lhs_row[2]), rhs_rows[2]));
+            _mm_add_ps(acc, _mm_mul_ps(_mm_set1_ps(lhs_row[3]), rhs_rows[3]))
+        });
+    }
+
+    out
+}
+
+pub(crate) fn mat4_scale(mat: &[[f32; 4]; 4], scale: f32) -> [[f32; 4]; 4] {
+    // SAFETY: SSE is always available on x86_64
+    let scale = unsafe { _mm_set1_ps(scale) };
+    let mut out = [[0.0; 4]; 4];
+
+    for (out_row, row) in out.iter_mut().zip(mat) {
+        // SAFETY: SSE is always available on x86_64
+        *out_row = store(unsafe { _mm_mul_ps(load(row), scale) });
+    }
+
+    out
+}
+
+pub(crate) fn mat4_vec4(mat: &[[f32; 4]; 4], vec: [f32; 4]) -> [f32; 4] {
+    // SAFETY: SSE is always available on x86_64
+    store(unsafe {
+        let mut acc = _mm_mul_ps(load(&mat[0]), _mm_set1_ps(vec[0]));
+        acc = _mm_add_ps(acc, _mm_mul_ps(load(&mat[1]), _mm_set1_ps(vec[1])));
+        acc = _mm_add_ps(acc, _mm_mul_ps(load(&mat[2]), _mm_set1_ps(vec[2])));
+        _mm_add_ps(acc, _mm_mul_ps(load(&mat[3]), _mm_set1_ps(vec[3])))
+    })
+}
+
+pub(crate) fn vec4_add(lhs: [f32; 4], rhs: [f32; 4]) -> [f32; 4] {
+    // SAFETY: SSE is always available on x86_64
+    store(unsafe { _mm_add_ps(load(&lhs), load(&rhs)) })
+}
+
+pub(crate) fn vec4_sub(lhs: [f32; 4], rhs: [f32; 4]) -> [f32; 4] {
+    // SAFETY: SSE is always available on x86_64
+    store(unsafe { _mm_sub_ps(load(&lhs), load(&rhs)) })
+}
+
+pub(crate) fn vec4_scale(vec: [f32; 4], scale: f32) -> [f32; 4] {
+    // SAFETY: SSE is always available on x86_64
+    store(unsafe { _mm_mul_ps(load(&vec), _mm_set1_ps(scale)) })
+}
+
+pub(crate) fn vec4_div(vec: [f32; 4], divisor: f32) -> [f32; 4] {
+    // SAFETY: SSE is always available on x86_64
+    store(unsafe { _mm_div_ps(load(&vec), _mm_set1_ps(divisor)) })
+}
+
+pub(crate) fn vec4_neg(vec: [f32; 4]) -> [f32; 4] {
+    // Flipping the sign bit is what scalar negation does, subtracting from 0 would lose -0.0
+    // SAFETY: SSE is always available on x86_64
+    store(unsafe { _mm_xor_ps(load(&vec), _mm_set1_ps(-0.0)) })
+}
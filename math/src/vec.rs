@@ -1,9 +1,14 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::{
+    iter::{Product, Sum},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
 
 use bytemuck::{Pod, Zeroable};
 use paste::paste;
 use petra_macros::swizzles;
 
+use crate::Quat;
+
 macro_rules! vector {
     ($({$name: ident, $fields: tt, [$($($aliases: ident),*);*], $swizzle_types: tt})*) => {
         $(
@@ -29,13 +34,7 @@ macro_rules! vector {
             }
         }
 
-        impl Add for $name {
-            type Output = $name;
-
-            fn add(self, rhs: Self) -> Self::Output {
-                $name::new($(self.$field + rhs.$field),*)
-            }
-        }
+        vector!(ops $name, [$($field),*]);
 
         impl AddAssign for $name {
             fn add_assign(&mut self, rhs: Self) {
@@ -43,28 +42,12 @@ macro_rules! vector {
             }
         }
 
-        impl Sub for $name {
-            type Output = $name;
-
-            fn sub(self, rhs: Self) -> Self::Output {
-                $name::new($(self.$field - rhs.$field),*)
-            }
-        }
-
         impl SubAssign for $name {
             fn sub_assign(&mut self, rhs: Self) {
                 *self = *self - rhs;
             }
         }
 
-        impl Mul<f32> for $name {
-            type Output = $name;
-
-            fn mul(self, rhs: f32) -> Self::Output {
-                $name::new($(self.$field * rhs),*)
-            }
-        }
-
         impl Mul<$name> for f32 {
             type Output = $name;
 
@@ -79,14 +62,6 @@ macro_rules! vector {
             }
         }
 
-        impl Div<f32> for $name {
-            type Output = $name;
-
-            fn div(self, rhs: f32) -> Self::Output {
-                $name::new($(self.$field / rhs),*)
-            }
-        }
-
         impl Div<$name> for f32 {
             type Output = $name;
 
@@ -101,11 +76,28 @@ macro_rules! vector {
             }
         }
 
-        impl Neg for $name {
-            type Output = $name;
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::ZERO, |acc, v| acc + v)
+            }
+        }
 
-            fn neg(self) -> Self::Output {
-                $name::new($(-self.$field),*)
+        impl<'a> Sum<&'a $name> for $name {
+            fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+                iter.copied().sum()
+            }
+        }
+
+        #[doc = "Multiplies the vectors component wise"]
+        impl Product for $name {
+            fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::fill(1.0), |acc, v| acc.component_mul(v))
+            }
+        }
+
+        impl<'a> Product<&'a $name> for $name {
+            fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+                iter.copied().product()
             }
         }
 
@@ -158,9 +150,131 @@ macro_rules! vector {
                 $name::new($(self.$field / other.$field),*)
             }
 
+            #[doc = "Linearly interpolates between `from` and `to`, `t` outside of 0 to 1 extrapolates"]
             pub fn lerp(from: $name, to: $name, t: f32) -> $name {
                 (to - from) * t + from
             }
+
+            #[doc = "Linearly interpolates between `from` and `to` with `t` clamped to 0 to 1"]
+            pub fn lerp_clamped(from: $name, to: $name, t: f32) -> $name {
+                $name::lerp(from, to, t.clamp(0.0, 1.0))
+            }
+
+            #[doc = "The `t` of each component that [`lerp`](Self::lerp) needs to give `value`"]
+            pub fn inverse_lerp(from: $name, to: $name, value: $name) -> $name {
+                (value - from).component_div(to - from)
+            }
+
+            #[doc = "The largest component, like the biggest axis of a scale"]
+            pub fn max_element(&self) -> f32 {
+                f32::NEG_INFINITY $(.max(self.$field))*
+            }
+
+            #[doc = "The smallest component"]
+            pub fn min_element(&self) -> f32 {
+                f32::INFINITY $(.min(self.$field))*
+            }
+
+            #[doc = "Adds all of the components together"]
+            pub fn sum(&self) -> f32 {
+                0.0 $(+ self.$field)*
+            }
+
+            #[doc = "Multiplies all of the components together"]
+            pub fn product(&self) -> f32 {
+                1.0 $(* self.$field)*
+            }
+
+            #[doc = "Raises each component to the power of `exp`, like gamma correction"]
+            pub fn powf(&self, exp: f32) -> $name {
+                $name::new($(self.$field.powf(exp)),*)
+            }
+
+            #[doc = "The square root of each component"]
+            pub fn sqrt(&self) -> $name {
+                $name::new($(self.$field.sqrt()),*)
+            }
+
+            #[doc = "`e` raised to the power of each component"]
+            pub fn exp(&self) -> $name {
+                $name::new($(self.$field.exp()),*)
+            }
+
+            #[doc = "The natural log of each component"]
+            pub fn ln(&self) -> $name {
+                $name::new($(self.$field.ln()),*)
+            }
+
+            #[doc = "`1 / x` of each component"]
+            pub fn recip(&self) -> $name {
+                $name::new($(self.$field.recip()),*)
+            }
+
+            #[doc = "Returns true if every component is neither infinite nor NaN"]
+            pub fn is_finite(&self) -> bool {
+                true $(&& self.$field.is_finite())*
+            }
+
+            #[doc = "Returns true if any component is NaN"]
+            pub fn is_nan(&self) -> bool {
+                false $(|| self.$field.is_nan())*
+            }
+        }
+
+        #[doc = "Sets every component to the same value like [`fill`](Self::fill)"]
+        impl From<f32> for $name {
+            fn from(val: f32) -> Self {
+                Self::fill(val)
+            }
+        }
+    };
+    (ops Vec4, $fields: tt) => {
+        // Vec4 uses the SSE versions of these when the simd feature is on
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        vector!(scalar_ops Vec4, $fields);
+    };
+    (ops $name: ident, $fields: tt) => {
+        vector!(scalar_ops $name, $fields);
+    };
+    (scalar_ops $name: ident, [$($field: ident),*]) => {
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                $name::new($(self.$field + rhs.$field),*)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                $name::new($(self.$field - rhs.$field),*)
+            }
+        }
+
+        impl Mul<f32> for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: f32) -> Self::Output {
+                $name::new($(self.$field * rhs),*)
+            }
+        }
+
+        impl Div<f32> for $name {
+            type Output = $name;
+
+            fn div(self, rhs: f32) -> Self::Output {
+                $name::new($(self.$field / rhs),*)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> Self::Output {
+                $name::new($(-self.$field),*)
+            }
         }
     };
     (getters [$($field: ident),*], [$($($alias: ident),*);*]) => {
@@ -249,6 +363,17 @@ impl Vec2 {
         f32::atan(v.y / v.x)
     }
 
+    /// Rotates counterclockwise around the origin by `angle` radians
+    pub fn rotate(&self, angle: f32) -> Vec2 {
+        let (sin, cos) = angle.sin_cos();
+        Vec2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Rotates counterclockwise around `center` by `angle` radians
+    pub fn rotate_around_point(&self, center: Vec2, angle: f32) -> Vec2 {
+        (*self - center).rotate(angle) + center
+    }
+
     pub fn to_array(self) -> [f32; 2] {
         [self.x, self.y]
     }
@@ -256,6 +381,57 @@ impl Vec2 {
     pub fn from_array(arr: [f32; 2]) -> Vec2 {
         Vec2::new(arr[0], arr[1])
     }
+
+    /// The raw bits of each component, for using vectors as `Hash + Eq` keys
+    ///
+    /// This is meant for deduplicating vertices, vectors only compare equal if they have the
+    /// exact same bits.<br>
+    /// That means `0.0` and `-0.0` are different keys and NaNs with the same bits are the same key.
+    pub fn to_bits(self) -> [u32; 2] {
+        [self.x.to_bits(), self.y.to_bits()]
+    }
+
+    /// The weights of `a`, `b`, and `c` that add up to `p`
+    ///
+    /// The weights sum to 1 and are all between 0 and 1 when `p` is inside the triangle.<br>
+    /// Returns `None` if the triangle has no area.
+    pub fn barycentric(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> Option<Vec3> {
+        let ab = b - a;
+        let ac = c - a;
+        let ap = p - a;
+        let denom = ab.cross(ac);
+
+        if denom == 0.0 {
+            return None;
+        }
+
+        let v = ap.cross(ac) / denom;
+        let w = ab.cross(ap) / denom;
+        Some(Vec3::new(1.0 - v - w, v, w))
+    }
+
+    /// The unsigned area of the triangle `a`, `b`, `c`, the same for either winding order
+    pub fn triangle_area(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+        (b - a).cross(c - a).abs() / 2.0
+    }
+
+    /// Width over height, for using a size as a camera's aspect ratio
+    pub fn aspect_ratio(self) -> f32 {
+        self.x / self.y
+    }
+
+    /// Converts a pixel position to normalized device coordinates
+    ///
+    /// Pixels start at the top left of a surface of `size` pixels, NDC goes from -1 to 1
+    /// with y pointing up so the top left pixel is `(-1, 1)` and the center is `(0, 0)`.
+    pub fn pixel_to_ndc(self, size: Vec2) -> Vec2 {
+        Vec2::new(self.x / size.x * 2.0 - 1.0, 1.0 - self.y / size.y * 2.0)
+    }
+
+    /// Converts normalized device coordinates to a pixel position, the inverse of [`Vec2::pixel_to_ndc`]
+    pub fn ndc_to_pixel(self, size: Vec2) -> Vec2 {
+        Vec2::new((self.x + 1.0) / 2.0 * size.x, (1.0 - self.y) / 2.0 * size.y)
+    }
 }
 
 impl Vec3 {
@@ -271,6 +447,19 @@ impl Vec3 {
         )
     }
 
+    /// Rotates around `axis` through the origin by `angle` radians
+    ///
+    /// Uses the same convention as [`Quat::from_axis_angle`], positive angles rotate
+    /// counterclockwise when looking down the axis towards the origin.
+    pub fn rotate_around(&self, axis: Vec3, angle: f32) -> Vec3 {
+        Quat::from_axis_angle(axis.normalize(), angle).rotate_vec3(*self)
+    }
+
+    /// Rotates around `axis` through `center` by `angle` radians, like an object orbiting `center`
+    pub fn rotate_around_point(&self, center: Vec3, axis: Vec3, angle: f32) -> Vec3 {
+        (*self - center).rotate_around(axis, angle) + center
+    }
+
     pub fn to_array(self) -> [f32; 3] {
         [self.x, self.y, self.z]
     }
@@ -279,6 +468,40 @@ impl Vec3 {
         Vec3::new(arr[0], arr[1], arr[2])
     }
 
+    /// The raw bits of each component, see [`Vec2::to_bits`] for the caveats of using them as keys
+    pub fn to_bits(self) -> [u32; 3] {
+        [self.x.to_bits(), self.y.to_bits(), self.z.to_bits()]
+    }
+
+    /// The weights of `a`, `b`, and `c` that add up to `p` projected onto the triangle's plane
+    ///
+    /// Returns `None` if the triangle has no area, see [`Vec2::barycentric`]
+    pub fn barycentric(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<Vec3> {
+        let ab = b - a;
+        let ac = c - a;
+        let ap = p - a;
+
+        let ab_ab = ab.dot(ab);
+        let ab_ac = ab.dot(ac);
+        let ac_ac = ac.dot(ac);
+        let ap_ab = ap.dot(ab);
+        let ap_ac = ap.dot(ac);
+        let denom = ab_ab * ac_ac - ab_ac * ab_ac;
+
+        if denom == 0.0 {
+            return None;
+        }
+
+        let v = (ac_ac * ap_ab - ab_ac * ap_ac) / denom;
+        let w = (ab_ab * ap_ac - ab_ac * ap_ab) / denom;
+        Some(Vec3::new(1.0 - v - w, v, w))
+    }
+
+    /// The unsigned area of the triangle `a`, `b`, `c`, half the length of its edges' cross product
+    pub fn triangle_area(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+        (b - a).cross(c - a).magnitude() / 2.0
+    }
+
     pub fn from_xy(xy: Vec2, z: f32) -> Vec3 {
         Vec3::new(xy.x, xy.y, z)
     }
@@ -286,6 +509,50 @@ impl Vec3 {
     pub fn from_yz(x: f32, yz: Vec2) -> Vec3 {
         Vec3::new(x, yz.x, yz.y)
     }
+
+    /// Converts an rgb color to hue, saturation, and value, all from 0 to 1
+    ///
+    /// Red has a hue of 0, green 1/3, and blue 2/3. Greys have a hue and saturation of 0.
+    pub fn rgb_to_hsv(&self) -> Vec3 {
+        let max = self.max_element();
+        let min = self.min_element();
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == self.x {
+            ((self.y - self.z) / delta).rem_euclid(6.0)
+        } else if max == self.y {
+            (self.z - self.x) / delta + 2.0
+        } else {
+            (self.x - self.y) / delta + 4.0
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        Vec3::new(hue / 6.0, saturation, max)
+    }
+
+    /// Converts a hue, saturation, and value color to rgb, the inverse of [`Vec3::rgb_to_hsv`]
+    ///
+    /// The hue wraps around so 1.25 is the same as 0.25, saturation and value are clamped to 0 to 1.
+    pub fn hsv_to_rgb(&self) -> Vec3 {
+        let hue = self.x.rem_euclid(1.0) * 6.0;
+        let saturation = self.y.clamp(0.0, 1.0);
+        let value = self.z.clamp(0.0, 1.0);
+
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Vec3::new(r, g, b) + Vec3::fill(value - chroma)
+    }
 }
 
 impl Vec4 {
@@ -302,6 +569,23 @@ impl Vec4 {
         Vec4::new(arr[0], arr[1], arr[2], arr[3])
     }
 
+    /// Converts each component to the bits of a half precision float for uploading to the gpu
+    ///
+    /// See [`f32_to_f16_bits`](crate::f32_to_f16_bits)
+    pub fn to_f16x4(self) -> [u16; 4] {
+        self.to_array().map(crate::f32_to_f16_bits)
+    }
+
+    /// The raw bits of each component, see [`Vec2::to_bits`] for the caveats of using them as keys
+    pub fn to_bits(self) -> [u32; 4] {
+        [
+            self.x.to_bits(),
+            self.y.to_bits(),
+            self.z.to_bits(),
+            self.w.to_bits(),
+        ]
+    }
+
     pub fn from_xyz(xyz: Vec3, w: f32) -> Vec4 {
         Vec4::new(xyz.x, xyz.y, xyz.z, w)
     }
@@ -326,3 +610,367 @@ impl Vec4 {
         Vec4::new(x, y, zw.x, zw.y)
     }
 }
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Add for Vec4 {
+    type Output = Vec4;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vec4::from_array(crate::simd::vec4_add(self.to_array(), rhs.to_array()))
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Sub for Vec4 {
+    type Output = Vec4;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec4::from_array(crate::simd::vec4_sub(self.to_array(), rhs.to_array()))
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Mul<f32> for Vec4 {
+    type Output = Vec4;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vec4::from_array(crate::simd::vec4_scale(self.to_array(), rhs))
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Div<f32> for Vec4 {
+    type Output = Vec4;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Vec4::from_array(crate::simd::vec4_div(self.to_array(), rhs))
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Neg for Vec4 {
+    type Output = Vec4;
+
+    fn neg(self) -> Self::Output {
+        Vec4::from_array(crate::simd::vec4_neg(self.to_array()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    macro_rules! assert_close {
+        ($a: expr, $b: expr) => {{
+            let (a, b) = ($a, $b);
+            assert!((a - b).magnitude() <= 1e-5, "{a} isn't close to {b}");
+        }};
+    }
+
+    /// Vectors with components in [-10, 10], the same every run
+    fn random_vec4s(count: usize) -> Vec<Vec4> {
+        // xorshift32
+        let mut state = 0x2545_F491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 20.0 - 10.0
+        };
+
+        (0 .. count)
+            .map(|_| Vec4::new(next(), next(), next(), next()))
+            .collect()
+    }
+
+    fn each(a: Vec4, b: Vec4, f: impl Fn(f32, f32) -> f32) -> [f32; 4] {
+        [f(a.x, b.x), f(a.y, b.y), f(a.z, b.z), f(a.w, b.w)]
+    }
+
+    // These run against both the scalar and SSE operators, neither uses fused multiply-add so
+    // they have to match plain f32 math exactly
+    #[test]
+    fn vec4_ops_match_scalar() {
+        for pair in random_vec4s(256).chunks_exact(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let s = b.x;
+
+            assert_eq!((a + b).to_array(), each(a, b, |a, b| a + b));
+            assert_eq!((a - b).to_array(), each(a, b, |a, b| a - b));
+            assert_eq!((a * s).to_array(), each(a, b, |a, _| a * s));
+            assert_eq!((s * a).to_array(), each(a, b, |a, _| a * s));
+            assert_eq!((a / s).to_array(), each(a, b, |a, _| a / s));
+            assert_eq!((-a).to_array(), each(a, b, |a, _| -a));
+        }
+    }
+
+    #[test]
+    fn vec4_neg_keeps_signed_zero() {
+        assert_eq!((-Vec4::ZERO).to_bits(), [(-0.0f32).to_bits(); 4]);
+        assert_eq!((-Vec4::fill(-0.0)).to_bits(), [0; 4]);
+    }
+
+    #[test]
+    fn sum_averages_positions() {
+        let positions = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 6.0),
+            Vec3::new(0.0, 3.0, -3.0),
+        ];
+
+        assert_eq!(
+            positions.iter().sum::<Vec3>() / 3.0,
+            Vec3::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            positions.into_iter().sum::<Vec3>(),
+            Vec3::new(3.0, 3.0, 3.0)
+        );
+        assert_eq!(std::iter::empty::<Vec2>().sum::<Vec2>(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn product_multiplies_component_wise() {
+        let scales = [Vec4::new(1.0, 2.0, 3.0, 4.0), Vec4::fill(2.0)];
+
+        assert_eq!(
+            scales.iter().product::<Vec4>(),
+            Vec4::new(2.0, 4.0, 6.0, 8.0)
+        );
+        assert_eq!(
+            std::iter::empty::<Vec4>().product::<Vec4>(),
+            Vec4::fill(1.0)
+        );
+    }
+
+    #[test]
+    fn to_bits_dedups_vertices() {
+        let positions = [
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+        ];
+
+        let mut indices = std::collections::HashMap::new();
+        let deduped: Vec<u32> = positions
+            .iter()
+            .map(|p| {
+                let next = indices.len() as u32;
+                *indices.entry(p.to_bits()).or_insert(next)
+            })
+            .collect();
+
+        assert_eq!(deduped, [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn to_bits_caveats() {
+        assert_ne!(
+            Vec2::new(0.0, 0.0).to_bits(),
+            Vec2::new(-0.0, 0.0).to_bits()
+        );
+        assert_eq!(
+            Vec4::fill(f32::NAN).to_bits(),
+            Vec4::fill(f32::NAN).to_bits()
+        );
+    }
+
+    #[test]
+    fn barycentric_weights() {
+        let (a, b, c) = (
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(0.0, 3.0),
+        );
+        let centroid = (a + b + c) / 3.0;
+
+        assert_close!(
+            Vec2::barycentric(centroid, a, b, c).unwrap(),
+            Vec3::fill(1.0 / 3.0)
+        );
+        assert_close!(Vec2::barycentric(a, a, b, c).unwrap(), Vec3::X);
+        assert_close!(Vec2::barycentric(c, a, b, c).unwrap(), Vec3::Z);
+        assert_eq!(Vec2::barycentric(a, a, b, b * 2.0), None);
+
+        let (a, b, c) = (
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let centroid = (a + b + c) / 3.0;
+
+        assert_close!(
+            Vec3::barycentric(centroid, a, b, c).unwrap(),
+            Vec3::fill(1.0 / 3.0)
+        );
+        assert_close!(Vec3::barycentric(b, a, b, c).unwrap(), Vec3::Y);
+        assert_eq!(Vec3::barycentric(a, a, b, b), None);
+    }
+
+    #[test]
+    fn triangle_areas() {
+        assert_eq!(
+            Vec2::triangle_area(Vec2::ZERO, Vec2::new(4.0, 0.0), Vec2::new(0.0, 3.0)),
+            6.0
+        );
+        assert_eq!(
+            Vec2::triangle_area(Vec2::ZERO, Vec2::new(0.0, 3.0), Vec2::new(4.0, 0.0)),
+            6.0
+        );
+        assert_eq!(
+            Vec3::triangle_area(
+                Vec3::ZERO,
+                Vec3::new(0.0, 4.0, 0.0),
+                Vec3::new(0.0, 0.0, 3.0)
+            ),
+            6.0
+        );
+        assert_eq!(Vec3::triangle_area(Vec3::ZERO, Vec3::X, Vec3::X * 2.0), 0.0);
+    }
+
+    #[test]
+    fn lerp_clamped_stays_between_ends() {
+        let (from, to) = (Vec3::ZERO, Vec3::new(2.0, 4.0, -2.0));
+
+        assert_eq!(Vec3::lerp(from, to, 1.5), Vec3::new(3.0, 6.0, -3.0));
+        assert_eq!(Vec3::lerp_clamped(from, to, 1.5), to);
+        assert_eq!(Vec3::lerp_clamped(from, to, -0.5), from);
+        assert_eq!(Vec3::lerp_clamped(from, to, 0.5), Vec3::new(1.0, 2.0, -1.0));
+    }
+
+    #[test]
+    fn inverse_lerp_undoes_lerp() {
+        let (from, to) = (
+            Vec4::new(1.0, 2.0, 3.0, 4.0),
+            Vec4::new(3.0, -2.0, 4.0, 0.0),
+        );
+        let t = Vec4::new(0.5, 0.25, 2.0, -1.0);
+        let value = Vec4::new(
+            Vec4::lerp(from, to, t.x).x,
+            Vec4::lerp(from, to, t.y).y,
+            Vec4::lerp(from, to, t.z).z,
+            Vec4::lerp(from, to, t.w).w,
+        );
+
+        assert_close!(Vec4::inverse_lerp(from, to, value), t);
+    }
+
+    #[test]
+    fn pixel_ndc_conversion() {
+        let size = Vec2::new(800.0, 600.0);
+
+        assert_eq!(Vec2::ZERO.pixel_to_ndc(size), Vec2::new(-1.0, 1.0));
+        assert_eq!(size.pixel_to_ndc(size), Vec2::new(1.0, -1.0));
+        assert_eq!((size / 2.0).pixel_to_ndc(size), Vec2::ZERO);
+        assert_eq!(Vec2::new(-1.0, 1.0).ndc_to_pixel(size), Vec2::ZERO);
+
+        let pixel = Vec2::new(123.0, 456.0);
+        assert_close!(pixel.pixel_to_ndc(size).ndc_to_pixel(size), pixel);
+        assert_eq!(size.aspect_ratio(), 4.0 / 3.0);
+    }
+
+    #[test]
+    fn reductions() {
+        let v = Vec3::new(1.0, 5.0, 3.0);
+
+        assert_eq!(v.max_element(), 5.0);
+        assert_eq!(v.min_element(), 1.0);
+        assert_eq!(v.sum(), 9.0);
+        assert_eq!(v.product(), 15.0);
+        assert_eq!(Vec4::new(-1.0, -2.0, -3.0, -4.0).max_element(), -1.0);
+        assert_eq!(Vec2::new(2.0, -3.0).product(), -6.0);
+    }
+
+    #[test]
+    fn rotate_around_axis_and_point() {
+        assert_close!(Vec3::X.rotate_around(Vec3::Z, FRAC_PI_2), Vec3::Y);
+        // The axis doesn't have to be normalized
+        assert_close!(Vec3::X.rotate_around(Vec3::Z * 3.0, FRAC_PI_2), Vec3::Y);
+        assert_close!(
+            Vec3::new(2.0, 1.0, 4.0).rotate_around_point(
+                Vec3::new(1.0, 1.0, 4.0),
+                Vec3::Z,
+                FRAC_PI_2
+            ),
+            Vec3::new(1.0, 2.0, 4.0)
+        );
+
+        assert_close!(Vec2::new(1.0, 0.0).rotate(FRAC_PI_2), Vec2::new(0.0, 1.0));
+        assert_close!(
+            Vec2::new(3.0, 2.0).rotate_around_point(Vec2::new(2.0, 2.0), FRAC_PI_2),
+            Vec2::new(2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn rgb_hsv_conversion() {
+        assert_eq!(
+            Vec3::new(1.0, 0.0, 0.0).rgb_to_hsv(),
+            Vec3::new(0.0, 1.0, 1.0)
+        );
+        assert_close!(
+            Vec3::new(0.0, 1.0, 0.0).rgb_to_hsv(),
+            Vec3::new(1.0 / 3.0, 1.0, 1.0)
+        );
+        assert_close!(
+            Vec3::new(0.0, 0.0, 0.5).rgb_to_hsv(),
+            Vec3::new(2.0 / 3.0, 1.0, 0.5)
+        );
+        // Greys have no hue or saturation
+        assert_eq!(Vec3::fill(0.4).rgb_to_hsv(), Vec3::new(0.0, 0.0, 0.4));
+
+        for rgb in [
+            Vec3::new(1.0, 0.5, 0.25),
+            Vec3::new(0.2, 0.9, 0.6),
+            Vec3::new(0.7, 0.1, 0.8),
+            Vec3::fill(0.3),
+            Vec3::ZERO,
+        ] {
+            assert_close!(rgb.rgb_to_hsv().hsv_to_rgb(), rgb);
+        }
+    }
+
+    #[test]
+    fn hsv_hue_wraps() {
+        assert_close!(
+            Vec3::new(1.25, 1.0, 1.0).hsv_to_rgb(),
+            Vec3::new(0.5, 1.0, 0.0)
+        );
+        assert_close!(
+            Vec3::new(-0.75, 1.0, 1.0).hsv_to_rgb(),
+            Vec3::new(0.5, 1.0, 0.0)
+        );
+        assert_close!(
+            Vec3::new(1.0, 1.0, 1.0).hsv_to_rgb(),
+            Vec3::new(1.0, 0.0, 0.0)
+        );
+        assert_close!(
+            Vec3::new(0.0, 2.0, 5.0).hsv_to_rgb(),
+            Vec3::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn component_wise_math() {
+        assert_eq!(Vec3::fill(4.0).sqrt(), Vec3::fill(2.0));
+        assert_eq!(Vec3::fill(2.0).recip(), Vec3::fill(0.5));
+        assert_eq!(Vec2::new(2.0, 3.0).powf(2.0), Vec2::new(4.0, 9.0));
+        assert_close!(
+            Vec4::new(0.0, 1.0, 2.0, -1.0).exp().ln(),
+            Vec4::new(0.0, 1.0, 2.0, -1.0)
+        );
+        assert_close!(Vec3::fill(1.0).exp(), Vec3::fill(std::f32::consts::E));
+    }
+
+    #[test]
+    fn from_f32_broadcasts() {
+        assert_eq!(Vec4::from(1.0), Vec4::new(1.0, 1.0, 1.0, 1.0));
+        assert_eq!(Vec3::from(2.0), Vec3::fill(2.0));
+
+        let v: Vec2 = 0.5.into();
+        assert_eq!(v, Vec2::new(0.5, 0.5));
+    }
+}
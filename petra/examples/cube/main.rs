@@ -1,5 +1,6 @@
 use bytemuck::{Pod, Zeroable};
 use petra::{
+    camera::{Camera, OrbitCamera},
     manager::RenderManager,
     texture::{Depth, FRAMEBUFFER},
     wgpu::{
@@ -12,7 +13,7 @@ use petra::{
     },
     Vertex,
 };
-use petra_math::{Mat4, Vec3};
+use petra_math::{GpuMat4, Mat4, Vec3};
 use wgpu::{Color, ShaderStages};
 use winit::{
     event::{Event, KeyboardInput, VirtualKeyCode, WindowEvent},
@@ -30,9 +31,9 @@ struct CubeVertex {
 #[derive(Clone, Copy, Pod, Zeroable)]
 #[repr(C, align(8))]
 struct ModelViewProjection {
-    model: Mat4,
-    proj: Mat4,
-    view: Mat4,
+    model: GpuMat4,
+    proj: GpuMat4,
+    view: GpuMat4,
 }
 
 fn main() {
@@ -90,6 +91,9 @@ fn main() {
         .texture()
         .build();
 
+    let mut camera = Camera::for_surface(&manager, Vec3::ZERO, Vec3::ZERO, f32::to_radians(45.0));
+    let mut orbit = OrbitCamera::new(Vec3::ZERO, 3.0);
+
     let _cube_pass = manager
         .render_pass_builder(Some("Cube Render Pass"))
         .add_color_attachment(FRAMEBUFFER, Some(Color::BLACK), true)
@@ -99,7 +103,9 @@ fn main() {
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent { window_id, event } =>
-            if window_id == manager.window.id() {
+            if window_id == manager.window().id() {
+                orbit.handle_event(&event);
+
                 match event {
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } =>
                         manager.resize(*new_inner_size),
@@ -116,26 +122,25 @@ fn main() {
                     _ => {}
                 }
             },
-        Event::MainEventsCleared => manager.window.request_redraw(),
+        Event::MainEventsCleared => manager.window().request_redraw(),
         Event::RedrawRequested(window_id) =>
-            if manager.window.id() == window_id {
-                
+            if manager.window().id() == window_id {
                 let theta = -std::f32::consts::FRAC_PI_4;
-                let size = manager.window.inner_size();
+                camera.update_aspect_ratio(&manager);
+                orbit.apply(&mut camera);
+
+                let model = Mat4::IDENTITY
+                    * Mat4::roation_eular_xyz(theta, theta, theta)
+                    * Mat4::scale(Vec3::fill(2.0));
+
+                // for_shader converts each matrix to the layout the shader's mat4x4s expect
                 manager.write_to_buffer(cube_transform_buffer, &[ModelViewProjection {
-                    model: Mat4::IDENTITY
-                        * Mat4::roation_eular_xyz(theta, theta, theta)
-                        * Mat4::scale(Vec3::fill(2.0)),
-                    proj: Mat4::perspective_projection(
-                        f32::to_radians(45.0),
-                        size.width as f32 / size.height as f32,
-                        0.1,
-                        100.0,
-                    ),
-                    view: Mat4::look_at(Vec3::new(0.0, 0.0, 3.0), Vec3::fill(0.0), Vec3::Y),
+                    model: model.for_shader(),
+                    proj: camera.projection().for_shader(),
+                    view: camera.view().for_shader(),
                 }]);
 
-                match manager.render() {
+                match manager.render_simple() {
                     Ok(_) => {}
                     Err(SurfaceError::Lost) | Err(SurfaceError::OutOfMemory) =>
                         *control_flow = ControlFlow::Exit,
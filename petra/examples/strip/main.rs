@@ -0,0 +1,128 @@
+use bytemuck::{Pod, Zeroable};
+use petra::{
+    manager::RenderManager,
+    wgpu::{FrontFace, IndexFormat, PrimitiveTopology},
+    Vertex,
+};
+use petra_math::{Vec2, Vec3};
+use wgpu::SurfaceError;
+use winit::{
+    event::{Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::Window,
+};
+
+// The same vertex layout as the triangle example so we can reuse its shader
+#[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+#[repr(C, align(8))]
+struct StripVertex {
+    pos: Vec2,
+    color: Vec3,
+    __padding: f32,
+}
+
+// An index of 0xFFFF in a u16 index buffer ends the current strip and starts a new one
+const RESTART: u16 = 0xFFFF;
+
+fn main() {
+    let event_loop = EventLoop::new();
+    let window = Window::new(&event_loop).expect("Error creating winit window");
+
+    let mut manager = pollster::block_on(RenderManager::new(window));
+
+    let shader = manager.register_shader(
+        include_str!("../triangle/triangle.wgsl"),
+        Some("Strip Shader"),
+    );
+
+    let vertex_buffer = manager
+        .buffer_builder::<StripVertex>(Some("Strip Vertex Buffer"))
+        .vertex()
+        .build_init(StripVertex::quads());
+
+    // Each quad is drawn as its own 4 vertex strip,
+    // without primitive restart the strips would be joined by extra triangles
+    let index_buffer = manager
+        .buffer_builder::<u16>(Some("Strip Index Buffer"))
+        .index()
+        .build_init(vec![0, 1, 2, 3, RESTART, 4, 5, 6, 7, RESTART, 8, 9, 10, 11]);
+
+    let strip_pipeline = manager
+        .render_pipeline_builder(Some("Strip Pipeline"))
+        .front_face(FrontFace::Cw)
+        .topology(PrimitiveTopology::TriangleStrip)
+        // This is what the index buffer would infer anyway, but it's
+        // what turns primitive restart on so we set it explicitly here
+        .strip_index_format(Some(IndexFormat::Uint16))
+        .vertex_shader(shader, "vs_main")
+        .fragment_shader(shader, "fs_main")
+        .add_vertex_buffer(vertex_buffer)
+        .add_index_buffer(index_buffer)
+        .build();
+
+    let _strip_pass = manager
+        .render_pass_builder(Some("Strip Render Pass"))
+        .add_pipeline(strip_pipeline)
+        .build();
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent { window_id, event } if window_id == manager.window().id() =>
+            match event {
+                WindowEvent::ScaleFactorChanged { new_inner_size, .. } =>
+                    manager.resize(*new_inner_size),
+                WindowEvent::Resized(size) => manager.resize(size),
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
+                            ..
+                        },
+                    ..
+                } => *control_flow = ControlFlow::Exit,
+                _ => {}
+            },
+        Event::MainEventsCleared => manager.window().request_redraw(),
+        Event::RedrawRequested(window_id) if manager.window().id() == window_id =>
+            match manager.render_simple() {
+                Ok(_) => {}
+                Err(SurfaceError::Lost) | Err(SurfaceError::OutOfMemory) =>
+                    *control_flow = ControlFlow::Exit,
+                Err(SurfaceError::Outdated) => manager.recreate(),
+                Err(SurfaceError::Timeout) => println!("Surface timed out"),
+            },
+        _ => {}
+    })
+}
+
+impl StripVertex {
+    // Three quads side by side, each with its own color
+    fn quads() -> Vec<StripVertex> {
+        let colors = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+
+        colors
+            .iter()
+            .enumerate()
+            .flat_map(|(i, color)| {
+                let left = -0.9 + i as f32 * 0.65;
+                let right = left + 0.5;
+
+                [
+                    Vec2::new(left, -0.5),
+                    Vec2::new(left, 0.5),
+                    Vec2::new(right, -0.5),
+                    Vec2::new(right, 0.5),
+                ]
+                .map(|pos| StripVertex {
+                    pos,
+                    color: *color,
+                    __padding: 0.0,
+                })
+            })
+            .collect()
+    }
+}
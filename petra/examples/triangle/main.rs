@@ -70,7 +70,7 @@ fn main() {
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent { window_id, event } =>
-            if window_id == manager.window.id() {
+            if window_id == manager.window().id() {
                 match event {
                     // If the window was resized we need to tell the manager
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } =>
@@ -90,11 +90,11 @@ fn main() {
                 }
             },
         // Once we have handeled all the events we want to redraw
-        Event::MainEventsCleared => manager.window.request_redraw(),
+        Event::MainEventsCleared => manager.window().request_redraw(),
         Event::RedrawRequested(window_id) =>
-            if manager.window.id() == window_id {
+            if manager.window().id() == window_id {
                 // Tell the manager to render to the screen
-                match manager.render() {
+                match manager.render_simple() {
                     Ok(_) => {}
                     // If the surface was lost or out of memeory it is a critical error
                     Err(SurfaceError::Lost) | Err(SurfaceError::OutOfMemory) =>
@@ -1,14 +1,14 @@
-use std::num::NonZeroU64;
+use std::{num::NonZeroU64, sync::Arc};
 
 use wgpu::{
     BindGroup as RawBindGroup,
     BindGroupDescriptor,
     BindGroupEntry,
     BindGroupLayout,
-    BindGroupLayoutDescriptor,
     BindGroupLayoutEntry,
     BindingResource,
     BindingType,
+    BufferBinding,
     BufferBindingType,
     Device,
     Label,
@@ -29,44 +29,68 @@ use crate::{
 
 pub type BindGroupHandle = Handle<BindGroup>;
 
+/// The kind of resource bound at a binding of a [`BindGroup`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    Buffer,
+    Texture,
+    Sampler,
+}
+
 pub struct BindGroup {
     name: Option<String>,
-    layout: BindGroupLayout,
+    layout: Arc<BindGroupLayout>,
     bind_group: RawBindGroup,
-    buffers: Vec<(u32, BufferHandle)>,
-    textures: Vec<(u32, TextureHandle)>,
+    buffers: Vec<(u32, BufferHandle, Option<NonZeroU64>)>,
+    textures: Vec<(u32, TextureHandle, TextureViewDimension)>,
     samplers: Vec<(u32, TextureSampleHandle)>,
+    /// Sorted by binding since that is the order wgpu expects the offsets in
+    dynamic_buffers: Vec<DynamicBuffer>,
+}
+
+/// A buffer binding that is offset into its buffer when the bind group is set
+struct DynamicBuffer {
+    binding: u32,
+    size: NonZeroU64,
+    offset: u32,
 }
 
 impl BindGroup {
     fn new(
         name: Label<'_>,
-        layout: BindGroupLayout,
-        buffers: Vec<(u32, BufferHandle)>,
-        textures: Vec<(u32, TextureHandle)>,
+        layout: Arc<BindGroupLayout>,
+        buffers: Vec<(u32, BufferHandle, Option<NonZeroU64>)>,
+        textures: Vec<(u32, TextureHandle, TextureViewDimension)>,
         samplers: Vec<(u32, TextureSampleHandle)>,
+        dynamic_buffers: Vec<DynamicBuffer>,
         manager: &mut RenderManager,
     ) -> Self {
         let mut entries = Vec::new();
         let mut views = Vec::new();
 
-        for (binding, buffer) in &buffers {
+        for (binding, buffer, min_size) in &buffers {
             let buffer = manager
                 .get_buffer(*buffer)
                 .expect("Invalid BufferHandle passed to BindGroupBuilder");
 
             entries.push(BindGroupEntry {
                 binding: *binding,
-                resource: BindingResource::Buffer(buffer.inner().as_entire_buffer_binding()),
+                resource: BindingResource::Buffer(buffer_binding(
+                    name,
+                    buffer,
+                    *binding,
+                    *min_size,
+                    &dynamic_buffers,
+                )),
             })
         }
 
-        for (binding, texture) in &textures {
+        for (binding, texture, dimension) in &textures {
             let texture = manager
                 .get_texture(*texture)
                 .expect("Invalid TextureHandle passed to BindGroupBuilder");
 
-            let view = texture.get_view();
+            let view = texture.get_view_with_dimension(*dimension);
 
             views.push((*binding, view));
         }
@@ -102,6 +126,7 @@ impl BindGroup {
             buffers,
             textures,
             samplers,
+            dynamic_buffers,
         }
     }
 
@@ -113,15 +138,91 @@ impl BindGroup {
         &self.layout
     }
 
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Every binding in the group and the kind of resource bound to it, sorted by binding
+    pub fn bindings(&self) -> Vec<(u32, BindingKind)> {
+        let mut bindings = self
+            .buffers
+            .iter()
+            .map(|(binding, ..)| (*binding, BindingKind::Buffer))
+            .chain(
+                self.textures
+                    .iter()
+                    .map(|(binding, ..)| (*binding, BindingKind::Texture)),
+            )
+            .chain(
+                self.samplers
+                    .iter()
+                    .map(|(binding, _)| (*binding, BindingKind::Sampler)),
+            )
+            .collect::<Vec<_>>();
+
+        bindings.sort_by_key(|(binding, _)| *binding);
+        bindings
+    }
+
+    /// Whether this bind group can be used in place of `other` in a pipeline
+    ///
+    /// Bind groups are compatible when they were built with the same set of layout entries,
+    /// meaning the same bindings with the same types, visibilities, and counts.<br>
+    /// The order the bindings were added in and the labels of the bind groups don't matter.
+    pub fn is_compatible(&self, other: &BindGroup) -> bool {
+        Arc::ptr_eq(&self.layout, &other.layout)
+    }
+
+    /// The offsets for every dynamic buffer in the group, in the order `set_bind_group` expects
+    pub(crate) fn dynamic_offsets(&self) -> Vec<u32> {
+        dynamic_offsets(&self.dynamic_buffers)
+    }
+
+    /// The number of bindings in the group with a dynamic offset
+    pub(crate) fn dynamic_count(&self) -> usize {
+        self.dynamic_buffers.len()
+    }
+
+    /// Sets the offset used for the dynamic buffer at `binding`
+    pub(crate) fn set_dynamic_offset(&mut self, binding: u32, offset: u32) {
+        let name = self.name.as_deref();
+        let buffer = self
+            .dynamic_buffers
+            .iter_mut()
+            .find(|b| b.binding == binding)
+            .unwrap_or_else(|| {
+                panic!("Bind group {name:?} doesn't have a dynamic buffer at binding {binding}")
+            });
+
+        buffer.offset = offset;
+    }
+
+    /// Moves every dynamic binding of `buffer` to `offset`
+    pub(crate) fn set_buffer_offset(&mut self, buffer: BufferHandle, offset: u32) {
+        for (binding, ..) in self.buffers.iter().filter(|(_, h, _)| *h == buffer) {
+            if let Some(dynamic) = self
+                .dynamic_buffers
+                .iter_mut()
+                .find(|b| b.binding == *binding)
+            {
+                dynamic.offset = offset;
+            }
+        }
+    }
+
     pub(crate) fn depends_texture(&self, texture: TextureHandle) -> bool {
-        self.textures.iter().any(|(_, h)| *h == texture)
+        self.textures.iter().any(|(_, h, _)| *h == texture)
     }
 
     pub(crate) fn depends_buffer(&self, buffer: BufferHandle) -> bool {
-        self.buffers.iter().any(|(_, h)| *h == buffer)
+        self.buffers.iter().any(|(_, h, _)| *h == buffer)
+    }
+
+    pub(crate) fn depends_sampler(&self, sampler: TextureSampleHandle) -> bool {
+        self.samplers.iter().any(|(_, h)| *h == sampler)
     }
 
-    /// Recreates the BindGroup in case some of the buffers or textures have been recreated
+    /// Recreates the BindGroup in case some of the buffers, textures, or samplers have been recreated
     pub(crate) fn recreate(
         &mut self,
         device: &Device,
@@ -132,23 +233,29 @@ impl BindGroup {
         let mut entries = Vec::new();
         let mut views = Vec::new();
 
-        for (binding, buffer) in &self.buffers {
+        for (binding, buffer, min_size) in &self.buffers {
             let buffer = buffers
                 .get(*buffer)
                 .expect("Invalid BufferHandle found when recreating BindGroup");
 
             entries.push(BindGroupEntry {
                 binding: *binding,
-                resource: BindingResource::Buffer(buffer.inner().as_entire_buffer_binding()),
+                resource: BindingResource::Buffer(buffer_binding(
+                    self.name.as_deref(),
+                    buffer,
+                    *binding,
+                    *min_size,
+                    &self.dynamic_buffers,
+                )),
             })
         }
 
-        for (binding, texture) in &self.textures {
+        for (binding, texture, dimension) in &self.textures {
             let texture = textures
                 .get(*texture)
                 .expect("Invalid TextureHandle found when recreating BindGroup");
 
-            let view = texture.get_view();
+            let view = texture.get_view_with_dimension(*dimension);
 
             views.push((*binding, view));
         }
@@ -181,14 +288,88 @@ impl BindGroup {
     }
 }
 
+/// Orders the dynamic buffers the way `set_bind_group` expects their offsets
+///
+/// That is by binding, counting only the entries with a dynamic offset.
+/// Panics if a dynamic entry in the layout has no buffer to take its offset from.
+fn order_dynamic_buffers(
+    group_name: Option<&str>,
+    entries: &[BindGroupLayoutEntry],
+    mut dynamic_buffers: Vec<DynamicBuffer>,
+) -> Vec<DynamicBuffer> {
+    let mut bindings = entries
+        .iter()
+        .filter(|e| {
+            matches!(e.ty, BindingType::Buffer {
+                has_dynamic_offset: true,
+                ..
+            })
+        })
+        .map(|e| e.binding)
+        .collect::<Vec<_>>();
+    bindings.sort_unstable();
+
+    bindings
+        .into_iter()
+        .map(|binding| {
+            let index = dynamic_buffers
+                .iter()
+                .position(|b| b.binding == binding)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Bind group {group_name:?} has a dynamic offset at binding {binding} but \
+                         no dynamic buffer bound there"
+                    )
+                });
+            dynamic_buffers.swap_remove(index)
+        })
+        .collect()
+}
+
+/// The offset of each dynamic buffer, in the same order as `dynamic_buffers`
+fn dynamic_offsets(dynamic_buffers: &[DynamicBuffer]) -> Vec<u32> {
+    dynamic_buffers.iter().map(|b| b.offset).collect()
+}
+
+/// Panics if the bound part of `buffer` is smaller than the layout's `min_binding_size`
+fn buffer_binding<'a>(
+    group_name: Option<&str>,
+    buffer: &'a Buffer,
+    binding: u32,
+    min_size: Option<NonZeroU64>,
+    dynamic_buffers: &[DynamicBuffer],
+) -> BufferBinding<'a> {
+    // Dynamic buffers only bind a window of the buffer so the offset has room to move it around
+    let size = dynamic_buffers
+        .iter()
+        .find(|b| b.binding == binding)
+        .map(|b| b.size);
+
+    if let Some(min_size) = min_size {
+        let bound_size = size.map_or(buffer.inner().size(), NonZeroU64::get);
+        assert!(
+            bound_size >= min_size.get(),
+            "Bind group {group_name:?} binds {bound_size} bytes of a buffer at {binding} but the \
+             binding needs at least {min_size} bytes, bind fewer elements or make the buffer \
+             bigger"
+        );
+    }
+
+    BufferBinding {
+        buffer: buffer.inner(),
+        offset: 0,
+        size,
+    }
+}
 
 pub struct BindGroupBuilder<'a> {
     name: Label<'a>,
     manager: &'a mut RenderManager,
     entries: Vec<BindGroupLayoutEntry>,
-    buffers: Vec<(u32, BufferHandle)>,
-    textures: Vec<(u32, TextureHandle)>,
+    buffers: Vec<(u32, BufferHandle, Option<NonZeroU64>)>,
+    textures: Vec<(u32, TextureHandle, TextureViewDimension)>,
     samplers: Vec<(u32, TextureSampleHandle)>,
+    dynamic_buffers: Vec<DynamicBuffer>,
 }
 
 impl<'a> BindGroupBuilder<'a> {
@@ -200,6 +381,7 @@ impl<'a> BindGroupBuilder<'a> {
             textures: Vec::new(),
             samplers: Vec::new(),
             buffers: Vec::new(),
+            dynamic_buffers: Vec::new(),
         }
     }
 
@@ -213,22 +395,85 @@ impl<'a> BindGroupBuilder<'a> {
             std::mem::size_of::<T>() as u64 % wgpu::MAP_ALIGNMENT == 0,
             "Data accessed by shaders must have an alignment of 8"
         );
+
+        // Buffers with multiple frames in flight are read through a dynamic offset
+        // that follows whichever frame was written last
+        let raw_buffer = self
+            .manager
+            .get_buffer(buffer)
+            .expect("Invalid BufferHandle passed to bind_uniform_buffer");
+        let frame = raw_buffer
+            .frame_size()
+            .map(|size| (size, raw_buffer.frame_offset()));
+
         self.entries.push(BindGroupLayoutEntry {
             binding,
             visibility,
             ty: BindingType::Buffer {
                 ty: BufferBindingType::Uniform,
-                has_dynamic_offset: false,
+                has_dynamic_offset: frame.is_some(),
                 min_binding_size: NonZeroU64::new(std::mem::size_of::<T>() as u64),
             },
             count: None,
         });
 
-        self.buffers.push((binding, buffer));
+        self.buffers.push((
+            binding,
+            buffer,
+            NonZeroU64::new(std::mem::size_of::<T>() as u64),
+        ));
+
+        if let Some((size, offset)) = frame {
+            self.dynamic_buffers.push(DynamicBuffer {
+                binding,
+                size,
+                offset,
+            });
+        }
+
+        self
+    }
+
+    /// Binds a window of one `T` into `buffer` that can be moved with [`RenderManager::set_dynamic_offset`]
+    ///
+    /// This is useful for storing the uniforms of many objects in one buffer,
+    /// each pipeline can read its own window with [`RenderManager::set_pipeline_dynamic_offsets`].<br>
+    /// The buffer's elements need to be padded to the device's `min_uniform_buffer_offset_alignment`.
+    pub fn bind_dynamic_uniform_buffer<T: BufferContents>(
+        mut self,
+        binding: u32,
+        visibility: ShaderStages,
+        buffer: BufferHandle,
+    ) -> Self {
+        let size = NonZeroU64::new(std::mem::size_of::<T>() as u64)
+            .expect("Cannot bind a zero sized type as a dynamic uniform buffer");
+
+        self.entries.push(BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(size),
+            },
+            count: None,
+        });
+
+        self.buffers.push((binding, buffer, Some(size)));
+        self.dynamic_buffers.push(DynamicBuffer {
+            binding,
+            size,
+            offset: 0,
+        });
 
         self
     }
 
+    /// Binds `buffer` as an array of `T`s that shaders can read and optionally write
+    ///
+    /// `num_elements` is the least number of elements the buffer must have, usually `None`
+    /// since the shader can check the array's length itself. The buffer is checked against it
+    /// whenever the bind group is created or recreated.
     pub fn bind_storage_buffer<T: BufferContents>(
         mut self,
         binding: u32,
@@ -241,23 +486,29 @@ impl<'a> BindGroupBuilder<'a> {
             std::mem::size_of::<T>() as u64 % wgpu::MAP_ALIGNMENT == 0,
             "Data accessed by shaders must have an alignment of 8"
         );
+        let min_binding_size =
+            num_elements.and_then(|size| NonZeroU64::new(size * std::mem::size_of::<T>() as u64));
+
         self.entries.push(BindGroupLayoutEntry {
             binding,
             visibility,
             ty: BindingType::Buffer {
                 ty: BufferBindingType::Storage { read_only },
                 has_dynamic_offset: false,
-                min_binding_size: num_elements
-                    .and_then(|size| NonZeroU64::new(size * std::mem::size_of::<T>() as u64)),
+                min_binding_size,
             },
             count: None,
         });
 
-        self.buffers.push((binding, buffer));
+        self.buffers.push((binding, buffer, min_binding_size));
 
         self
     }
 
+    /// Binds `texture` viewed with the dimension `view_dimension`
+    ///
+    /// The dimension can differ from the texture's own, like viewing a 6 layer array as a cube.
+    /// Use [`bind_texture_auto`](Self::bind_texture_auto) to use the texture's own dimension.
     pub fn bind_texture(
         mut self,
         binding: u32,
@@ -267,6 +518,11 @@ impl<'a> BindGroupBuilder<'a> {
         multisampled: bool,
         texture: TextureHandle,
     ) -> Self {
+        self.manager
+            .get_texture(texture)
+            .expect("Invalid texture handle passed to bind_texture")
+            .validate_view_dimension(binding, view_dimension);
+
         self.entries.push(BindGroupLayoutEntry {
             binding,
             visibility,
@@ -278,11 +534,36 @@ impl<'a> BindGroupBuilder<'a> {
             count: None,
         });
 
-        self.textures.push((binding, texture));
+        self.textures.push((binding, texture, view_dimension));
 
         self
     }
 
+    /// Binds `texture` with the view dimension it was created with
+    pub fn bind_texture_auto(
+        self,
+        binding: u32,
+        visibility: ShaderStages,
+        sample_type: TextureSampleType,
+        multisampled: bool,
+        texture: TextureHandle,
+    ) -> Self {
+        let view_dimension = self
+            .manager
+            .get_texture(texture)
+            .expect("Invalid texture handle passed to bind_texture_auto")
+            .view_dimension();
+
+        self.bind_texture(
+            binding,
+            visibility,
+            sample_type,
+            view_dimension,
+            multisampled,
+            texture,
+        )
+    }
+
     pub fn bind_storage_texture(
         mut self,
         binding: u32,
@@ -291,11 +572,12 @@ impl<'a> BindGroupBuilder<'a> {
         view_dimension: TextureViewDimension,
         texture: TextureHandle,
     ) -> Self {
-        let format = self
+        let texture_ref = self
             .manager
             .get_texture(texture)
-            .expect("Invalid texture handle passed to bind_storage_texture")
-            .format();
+            .expect("Invalid texture handle passed to bind_storage_texture");
+        texture_ref.validate_view_dimension(binding, view_dimension);
+        let format = texture_ref.format();
 
         self.entries.push(BindGroupLayoutEntry {
             binding,
@@ -308,7 +590,7 @@ impl<'a> BindGroupBuilder<'a> {
             count: None,
         });
 
-        self.textures.push((binding, texture));
+        self.textures.push((binding, texture, view_dimension));
 
         self
     }
@@ -318,6 +600,7 @@ impl<'a> BindGroupBuilder<'a> {
         binding: u32,
         visibility: ShaderStages,
         kind: SamplerBindingType,
+        sampler: TextureSampleHandle,
     ) -> Self {
         self.entries.push(BindGroupLayoutEntry {
             binding,
@@ -326,17 +609,30 @@ impl<'a> BindGroupBuilder<'a> {
             count: None,
         });
 
+        self.samplers.push((binding, sampler));
+
         self
     }
 
     pub fn build(self) -> BindGroupHandle {
+        for (binding, sampler) in &self.samplers {
+            let kind = self.entries.iter().find_map(|e| match e.ty {
+                BindingType::Sampler(kind) if e.binding == *binding => Some(kind),
+                _ => None,
+            });
+
+            if let Some(kind) = kind {
+                self.manager
+                    .get_sampler(*sampler)
+                    .expect("Invalid TextureSamplerHandle passed to BindGroupBuilder")
+                    .validate_binding_type(*binding, kind);
+            }
+        }
+
+        let dynamic_buffers = order_dynamic_buffers(self.name, &self.entries, self.dynamic_buffers);
         let layout = self
             .manager
-            .device
-            .create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: self.name,
-                entries: &self.entries,
-            });
+            .get_or_create_bind_group_layout(self.name, self.entries);
 
         let group = BindGroup::new(
             self.name,
@@ -344,8 +640,66 @@ impl<'a> BindGroupBuilder<'a> {
             self.buffers,
             self.textures,
             self.samplers,
+            dynamic_buffers,
             self.manager,
         );
         self.manager.add_bind_group(group)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamic_buffer(binding: u32, offset: u32) -> DynamicBuffer {
+        DynamicBuffer {
+            binding,
+            size: NonZeroU64::new(16).unwrap(),
+            offset,
+        }
+    }
+
+    fn layout_entry(binding: u32, ty: BindingType) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::VERTEX_FRAGMENT,
+            ty,
+            count: None,
+        }
+    }
+
+    fn dynamic_uniform(binding: u32) -> BindGroupLayoutEntry {
+        layout_entry(binding, BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: None,
+        })
+    }
+
+    #[test]
+    fn dynamic_offsets_skip_non_dynamic_bindings() {
+        let texture = layout_entry(1, BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        });
+        // Bound out of order like a builder could
+        let entries = [dynamic_uniform(2), texture, dynamic_uniform(0)];
+        let buffers = order_dynamic_buffers(None, &entries, vec![
+            dynamic_buffer(2, 512),
+            dynamic_buffer(0, 256),
+        ]);
+
+        let bindings: Vec<_> = buffers.iter().map(|b| b.binding).collect();
+        assert_eq!(bindings, [0, 2]);
+        assert_eq!(dynamic_offsets(&buffers), [256, 512]);
+    }
+
+    #[test]
+    #[should_panic(expected = "dynamic offset at binding 2")]
+    fn dynamic_binding_without_a_buffer_panics() {
+        order_dynamic_buffers(None, &[dynamic_uniform(0), dynamic_uniform(2)], vec![
+            dynamic_buffer(0, 256),
+        ]);
+    }
+}
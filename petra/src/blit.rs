@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use wgpu::{
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingResource,
+    BindingType,
+    Color,
+    CommandEncoder,
+    Device,
+    FilterMode,
+    FragmentState,
+    LoadOp,
+    MultisampleState,
+    Operations,
+    PipelineLayout,
+    PipelineLayoutDescriptor,
+    PrimitiveState,
+    RenderPassColorAttachment,
+    RenderPassDescriptor,
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    Sampler,
+    SamplerBindingType,
+    SamplerDescriptor,
+    ShaderModule,
+    ShaderModuleDescriptor,
+    ShaderSource,
+    ShaderStages,
+    TextureFormat,
+    TextureSampleType,
+    TextureView,
+    TextureViewDimension,
+    VertexState,
+};
+
+/// The fullscreen triangle pipeline and sampler shared by everything that copies one texture
+/// into another with a draw
+///
+/// Pipelines depend on the format of the target so one is created the first time each format is
+/// used and kept around after that.
+pub(crate) struct BlitCache {
+    shader: ShaderModule,
+    sampler: Sampler,
+    bind_group_layout: BindGroupLayout,
+    pipeline_layout: PipelineLayout,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl BlitCache {
+    pub(crate) fn new(device: &Device) -> BlitCache {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Blit Shader"),
+            source: ShaderSource::Wgsl(include_str!("blit.wgsl").into()),
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Blit Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Blit Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        BlitCache {
+            shader,
+            sampler,
+            bind_group_layout,
+            pipeline_layout,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    fn pipeline(&mut self, device: &Device, format: TextureFormat) -> &RenderPipeline {
+        self.pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Blit Pipeline"),
+                layout: Some(&self.pipeline_layout),
+                vertex: VertexState {
+                    module: &self.shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                fragment: Some(FragmentState {
+                    module: &self.shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+        })
+    }
+
+    /// Records a draw that stretches `source` over all of `target`
+    ///
+    /// `source` has to be a single 2d view of a filterable float texture
+    /// and `target_format` the format of `target`.
+    pub(crate) fn blit(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &TextureView,
+        target: &TextureView,
+        target_format: TextureFormat,
+    ) {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Blit Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(source),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        let pipeline = self.pipeline(device, target_format);
+
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Blit Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0 .. 3, 0 .. 1);
+    }
+}
@@ -1,7 +1,9 @@
 use std::{
     any::{Any, TypeId},
     marker::PhantomData,
-    sync::Arc,
+    num::NonZeroU64,
+    ops::{Deref, DerefMut},
+    sync::{mpsc, Arc},
 };
 
 use bytemuck::{Pod, Zeroable};
@@ -10,9 +12,13 @@ use wgpu::{
     Buffer as RawBuffer,
     BufferDescriptor,
     BufferUsages,
+    BufferViewMut,
+    CommandEncoderDescriptor,
     Device,
     IndexFormat,
     Label,
+    Maintain,
+    MapMode,
     Queue,
     VertexBufferLayout,
     VertexStepMode,
@@ -40,9 +46,54 @@ pub struct Buffer {
     buffer: RawBuffer,
     type_id: TypeId,
     element_size: u64,
+    /// The number of elements asked for, which can be less than fits in the allocation
+    len: u64,
     queue: Arc<Queue>,
     device: Arc<Device>,
     vertex_format: Option<VertexBufferLayout<'static>>,
+    check_nan: bool,
+    frames: FramesInFlight,
+}
+
+/// Where [`Buffer::append`] puts new elements and how big the grown buffer is
+#[derive(Debug, PartialEq)]
+struct AppendLayout {
+    /// The size of the existing elements, which is where the new ones start
+    offset: u64,
+    size: u64,
+    len: u64,
+}
+
+impl AppendLayout {
+    fn new(len: u64, element_size: u64, appended: u64) -> AppendLayout {
+        let offset = len * element_size;
+
+        AppendLayout {
+            offset,
+            size: offset + appended * element_size,
+            len: len + appended,
+        }
+    }
+}
+
+/// Tracks which region of an N-buffered uniform buffer is currently in use
+#[derive(Clone, Copy)]
+struct FramesInFlight {
+    count: u32,
+    current: u32,
+    /// The size of each region including padding to the dynamic offset alignment
+    stride: u64,
+    /// The size of the data in each region
+    size: u64,
+}
+
+impl FramesInFlight {
+    const SINGLE: FramesInFlight = FramesInFlight {
+        count: 1,
+        current: 0,
+        stride: 0,
+        size: 0,
+    };
 }
 
 impl Buffer {
@@ -52,6 +103,7 @@ impl Buffer {
         size: u64,
         usage: BufferUsages,
         vertex_format: Option<VertexBufferLayout<'static>>,
+        mapped_at_creation: bool,
     ) -> Buffer {
         debug_assert!(
             size % wgpu::MAP_ALIGNMENT == 0,
@@ -61,17 +113,20 @@ impl Buffer {
             label,
             size,
             usage,
-            mapped_at_creation: false,
+            mapped_at_creation,
         });
 
         Buffer {
             buffer: raw,
             type_id: TypeId::of::<T>(),
             element_size: std::mem::size_of::<T>() as u64,
+            len: size / std::mem::size_of::<T>() as u64,
             queue: manager.queue.clone(),
             device: manager.device.clone(),
             name: label.map(|s| s.to_owned()),
             vertex_format,
+            check_nan: false,
+            frames: FramesInFlight::SINGLE,
         }
     }
 
@@ -79,23 +134,26 @@ impl Buffer {
         manager: &mut RenderManager,
         label: Label<'_>,
         usage: BufferUsages,
-        data: Vec<T>,
+        data: &[T],
         vertex_format: Option<VertexBufferLayout<'static>>,
     ) -> Buffer {
         let raw = manager.device.create_buffer_init(&BufferInitDescriptor {
             label,
             usage,
-            contents: bytemuck::cast_slice(&data),
+            contents: bytemuck::cast_slice(data),
         });
 
         Buffer {
             buffer: raw,
             type_id: TypeId::of::<T>(),
             element_size: std::mem::size_of::<T>() as u64,
+            len: data.len() as u64,
             queue: manager.queue.clone(),
             device: manager.device.clone(),
             name: label.map(|s| s.to_owned()),
             vertex_format,
+            check_nan: false,
+            frames: FramesInFlight::SINGLE,
         }
     }
 
@@ -107,7 +165,14 @@ impl Buffer {
         }
         let byte_slice = bytemuck::cast_slice(data);
 
-        if byte_slice.len() as u64 > self.buffer.size() {
+        if cfg!(debug_assertions) && self.check_nan {
+            check_nan(self.name.as_deref(), byte_slice);
+        }
+
+        if self.frames.count > 1 {
+            self.write_next_frame(byte_slice);
+            false
+        } else if byte_slice.len() as u64 > self.buffer.size() {
             let usage = self.buffer.usage();
             let old_buf = std::mem::replace(
                 &mut self.buffer,
@@ -119,19 +184,215 @@ impl Buffer {
             );
 
             old_buf.destroy();
+            self.len = data.len() as u64;
             true
         } else {
+            self.validate_usages(BufferUsages::COPY_DST, "be written to");
             self.queue.write_buffer(&self.buffer, 0, byte_slice);
+            self.len = self.len.max(data.len() as u64);
             false
         }
     }
 
+    /// Grows the buffer to fit `data` after its current elements, copying the old elements over
+    ///
+    /// The data goes right after the last element, not after the end of the allocation,
+    /// so padding or space reserved with [`BufferBuilder::build`] isn't kept.<br>
+    /// The buffer keeps its usages and vertex format but is always recreated, use
+    /// [`RenderManager::append_to_buffer`] so bind groups using it are recreated too.
+    pub fn append<T: BufferContents>(&mut self, data: &[T]) {
+        if TypeId::of::<T>() != self.type_id {
+            panic!(
+                "Attempted to append to buffer with a different type than it was initialized with"
+            );
+        }
+        assert!(
+            self.frames.count == 1,
+            "Tried to append to buffer {:?} which has multiple frames in flight, buffers with \
+             multiple frames in flight can't be resized",
+            self.name
+        );
+        self.validate_usages(
+            BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            "be appended to",
+        );
+
+        let byte_slice: &[u8] = bytemuck::cast_slice(data);
+        let layout = AppendLayout::new(self.len, self.element_size, data.len() as u64);
+        assert!(
+            layout.offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+                && (byte_slice.len() as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "Tried to append {} bytes after {} bytes of buffer {:?}, both must be a multiple of \
+             {} bytes",
+            byte_slice.len(),
+            layout.offset,
+            self.name,
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        if cfg!(debug_assertions) && self.check_nan {
+            check_nan(self.name.as_deref(), byte_slice);
+        }
+
+        let new_buf = self.device.create_buffer(&BufferDescriptor {
+            label: self.name.as_deref(),
+            size: layout.size,
+            usage: self.buffer.usage(),
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Buffer Append"),
+            });
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &new_buf, 0, layout.offset);
+        self.queue.write_buffer(&new_buf, layout.offset, byte_slice);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        // wgpu waits for the copy to finish before actually freeing the old buffer
+        std::mem::replace(&mut self.buffer, new_buf).destroy();
+        self.len = layout.len;
+    }
+
+    /// Copies the buffer's elements back to the cpu, blocking until the gpu is done
+    ///
+    /// Buffers without `MAP_READ` are copied into a staging buffer first, which is how storage
+    /// buffers have to be read since they can't be mapped.<br>
+    /// Buffers with multiple frames in flight only read the region last written to.
+    pub(crate) fn read_data<T: BufferContents>(&self) -> Vec<T> {
+        if TypeId::of::<T>() != self.type_id {
+            panic!("Attempted to read buffer with a different type than it was initialized with");
+        }
+
+        let staging = if self.usages().contains(BufferUsages::MAP_READ) {
+            None
+        } else {
+            self.validate_usages(BufferUsages::COPY_SRC, "be read back without .map_read()");
+
+            let staging = self.device.create_buffer(&BufferDescriptor {
+                label: Some("Buffer Readback Buffer"),
+                size: self.buffer.size(),
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("Buffer Readback Encoder"),
+                });
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &staging, 0, self.buffer.size());
+            self.queue.submit(std::iter::once(encoder.finish()));
+            Some(staging)
+        };
+        let buffer = staging.as_ref().unwrap_or(&self.buffer);
+
+        let (sender, receiver) = mpsc::channel();
+        let slice = buffer.slice(..);
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(Maintain::Wait);
+        receiver
+            .recv()
+            .expect("Buffer readback was never mapped")
+            .unwrap_or_else(|e| panic!("Failed to read back buffer {:?}: {e}", self.name));
+
+        let (offset, size) = if self.frames.count > 1 {
+            (self.frame_offset() as u64, self.frames.size)
+        } else {
+            (0, self.len * self.element_size)
+        };
+
+        let mut data = vec![T::zeroed(); (size / self.element_size) as usize];
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut data);
+        let view = slice.get_mapped_range();
+        bytes.copy_from_slice(&view[offset as usize .. offset as usize + bytes.len()]);
+
+        drop(view);
+        buffer.unmap();
+        data
+    }
+
+    /// Writes to the next region of an N-buffered buffer so the gpu can keep reading the last one
+    fn write_next_frame(&mut self, data: &[u8]) {
+        assert!(
+            data.len() as u64 <= self.frames.size,
+            "Tried to write {} bytes to buffer {:?} which only has {} bytes per frame, buffers \
+             with multiple frames in flight can't be resized",
+            data.len(),
+            self.name,
+            self.frames.size
+        );
+        self.validate_usages(BufferUsages::COPY_DST, "be written to");
+
+        self.frames.current = (self.frames.current + 1) % self.frames.count;
+        self.queue
+            .write_buffer(&self.buffer, self.frame_offset() as u64, data);
+    }
+
+    /// The number of copies of the data kept for frames in flight
+    pub fn frames_in_flight(&self) -> u32 {
+        self.frames.count
+    }
+
+    /// The offset of the region that was last written to
+    pub(crate) fn frame_offset(&self) -> u32 {
+        (self.frames.current as u64 * self.frames.stride) as u32
+    }
+
+    /// The size of each region if the buffer has multiple frames in flight
+    pub(crate) fn frame_size(&self) -> Option<NonZeroU64> {
+        if self.frames.count > 1 {
+            NonZeroU64::new(self.frames.size)
+        } else {
+            None
+        }
+    }
+
+    pub fn usages(&self) -> BufferUsages {
+        self.buffer.usage()
+    }
+
+    /// Panics naming the missing [`BufferBuilder`] methods if the buffer lacks any of `required`
+    ///
+    /// `action` should finish the sentence "buffer 'X' must be created with .foo() to ..."
+    pub(crate) fn validate_usages(&self, required: BufferUsages, action: &str) {
+        const BUILDER_METHODS: &[(BufferUsages, &str)] = &[
+            (BufferUsages::MAP_READ, ".map_read()"),
+            (BufferUsages::MAP_WRITE, ".map_write()"),
+            (BufferUsages::COPY_SRC, ".copy_src()"),
+            (BufferUsages::COPY_DST, ".copy_dst()"),
+            (BufferUsages::INDEX, ".index()"),
+            (BufferUsages::VERTEX, ".vertex()"),
+            (BufferUsages::UNIFORM, ".uniform()"),
+            (BufferUsages::STORAGE, ".storage()"),
+            (BufferUsages::INDIRECT, ".indirect()"),
+        ];
+
+        let missing = required - self.usages();
+
+        if !missing.is_empty() {
+            let methods = BUILDER_METHODS
+                .iter()
+                .filter(|(usage, _)| missing.contains(*usage))
+                .map(|(_, method)| *method)
+                .collect::<Vec<_>>()
+                .join(" and ");
+            panic!(
+                "buffer '{}' must be created with {methods} to {action}",
+                self.name.as_deref().unwrap_or("unnamed")
+            );
+        }
+    }
+
     pub(crate) fn inner(&self) -> &RawBuffer {
         &self.buffer
     }
 
     pub(crate) fn len(&self) -> u64 {
-        self.buffer.size() / self.element_size
+        self.len
     }
 
     pub(crate) fn vertex_format(&self) -> Option<VertexBufferLayout<'static>> {
@@ -151,11 +412,24 @@ impl Buffer {
     }
 }
 
+/// Panics if any of the f32s in `data` are NaN
+///
+/// NaNs in a transform will usually make a whole draw disappear, so it's much easier to catch them here
+fn check_nan(name: Option<&str>, data: &[u8]) {
+    let floats: &[f32] = bytemuck::cast_slice(data);
+
+    if let Some(i) = floats.iter().position(|f| f.is_nan()) {
+        panic!("Found a NaN at float {i} when writing to buffer {name:?}");
+    }
+}
+
 pub struct BufferBuilder<'a, T: BufferContents> {
     usages: BufferUsages,
     label: Label<'a>,
     manager: &'a mut RenderManager,
     vertex_format: Option<VertexBufferLayout<'static>>,
+    check_nan: bool,
+    frames_in_flight: u32,
     __buffer_type: PhantomData<T>,
 }
 
@@ -187,37 +461,224 @@ impl<'a, T: BufferContents> BufferBuilder<'a, T> {
             label,
             manager,
             vertex_format: None,
+            check_nan: false,
+            frames_in_flight: 1,
             __buffer_type: PhantomData,
         }
     }
 
+    /// Panic in debug builds if any data written to the buffer contains a NaN
+    ///
+    /// This treats every 4 bytes of the data as an f32,
+    /// so it should only be used with types made entirely of f32s like vectors and matrices.
+    pub fn check_nan(mut self) -> Self {
+        debug_assert!(
+            std::mem::align_of::<T>() >= 4,
+            "check_nan can only be used on buffers whose type is made of f32s"
+        );
+        self.check_nan = true;
+        self
+    }
+
+    /// Keep `frames` copies of a uniform buffer's data, each write goes to the next copy
+    ///
+    /// Bind groups read whichever copy was written last through a dynamic offset,
+    /// so the gpu can still be reading the previous frame's data while the next frame is written.<br>
+    /// This trades `frames` times the memory for fewer stalls on rapidly updated uniforms.
+    /// Only [`BufferBuilder::build`] supports this, buffers are single buffered by default.
+    pub fn frames_in_flight(mut self, frames: u32) -> Self {
+        assert!(frames > 0, "A buffer needs at least one frame in flight");
+        self.frames_in_flight = frames;
+        self
+    }
+
+    /// Builds an uninitialized buffer with space for `count` elements of `T`
+    ///
+    /// `count` is a number of elements, not bytes, so a uniform buffer holding one struct is
+    /// built with `build(1)`.
     pub fn build(self, count: u64) -> BufferHandle {
         let size = count * std::mem::size_of::<T>() as u64;
 
-        let buffer = Buffer::new::<T>(
+        let frames = if self.frames_in_flight > 1 {
+            debug_assert!(
+                self.usages.contains(BufferUsages::UNIFORM),
+                "Only uniform buffers can have multiple frames in flight"
+            );
+
+            let alignment = self
+                .manager
+                .device
+                .limits()
+                .min_uniform_buffer_offset_alignment;
+            FramesInFlight {
+                count: self.frames_in_flight,
+                current: 0,
+                stride: size.next_multiple_of(alignment as u64),
+                size,
+            }
+        } else {
+            FramesInFlight::SINGLE
+        };
+
+        let mut buffer = Buffer::new::<T>(
+            self.manager,
+            self.label,
+            size.max(frames.stride * frames.count as u64),
+            self.usages,
+            self.vertex_format,
+            false,
+        );
+        buffer.check_nan = self.check_nan;
+        buffer.frames = frames;
+        buffer.len = count;
+
+        self.manager.add_buffer(buffer)
+    }
+
+    /// Builds a buffer with space for `count` elements that all start as zero
+    ///
+    /// wgpu already zero initializes every buffer so [`build`](Self::build) starts out the same,
+    /// this spells it out for atomic counters and buffers a shader accumulates into.<br>
+    /// The buffer is padded out like [`build_mapped`](Self::build_mapped) so any `count` works.
+    pub fn build_zeroed(self, count: u64) -> BufferHandle {
+        debug_assert!(
+            self.frames_in_flight == 1,
+            "frames_in_flight is only supported by BufferBuilder::build"
+        );
+
+        // Buffers mapped at creation need a size that is a multiple of the map alignment
+        let size = (count * std::mem::size_of::<T>() as u64)
+            .next_multiple_of(wgpu::MAP_ALIGNMENT)
+            .max(wgpu::MAP_ALIGNMENT);
+
+        let mut buffer = Buffer::new::<T>(
             self.manager,
             self.label,
             size,
             self.usages,
             self.vertex_format,
+            true,
         );
+        buffer.buffer.slice(..).get_mapped_range_mut().fill(0);
+        buffer.buffer.unmap();
+        buffer.check_nan = self.check_nan;
+        buffer.len = count;
 
         self.manager.add_buffer(buffer)
     }
 
-    pub fn build_init(self, init_data: Vec<T>) -> BufferHandle {
-        let buffer = Buffer::new_init(
+    /// Builds a buffer with space for `count` elements that is mapped so it can be written to directly
+    ///
+    /// This avoids building the data in a separate `Vec` first like [`BufferBuilder::build_init`] needs.<br>
+    /// The buffer is unmapped when the returned [`MappedBuffer`] is dropped
+    /// and can't be used by the gpu until then.
+    pub fn build_mapped(self, count: u64) -> (BufferHandle, MappedBuffer<'a, T>) {
+        debug_assert!(
+            self.frames_in_flight == 1,
+            "frames_in_flight is only supported by BufferBuilder::build"
+        );
+
+        // Mapped buffers need a size that is a multiple of the map alignment,
+        // pad it out and only expose the part that was asked for
+        let size = (count * std::mem::size_of::<T>() as u64)
+            .next_multiple_of(wgpu::MAP_ALIGNMENT)
+            .max(wgpu::MAP_ALIGNMENT);
+
+        let mut buffer = Buffer::new::<T>(
+            self.manager,
+            self.label,
+            size,
+            self.usages,
+            self.vertex_format,
+            true,
+        );
+        buffer.len = count;
+        let handle = self.manager.add_buffer(buffer);
+
+        let manager: &'a RenderManager = self.manager;
+        let buffer = manager.get_buffer(handle).unwrap().inner();
+        let view = buffer.slice(..).get_mapped_range_mut();
+
+        (handle, MappedBuffer {
+            buffer,
+            view: Some(view),
+            len: count as usize,
+            __buffer_type: PhantomData,
+        })
+    }
+
+    /// Builds the buffer filled with `init_data`, which can be a `Vec`, array, or slice
+    ///
+    /// ```no_run
+    /// # use petra::manager::RenderManager;
+    /// # fn build(manager: &mut RenderManager) {
+    /// let indices = [0u16, 1, 2];
+    /// let vec = indices.to_vec();
+    ///
+    /// manager.buffer_builder::<u16>(None).build_init(indices);
+    /// manager.buffer_builder::<u16>(None).build_init(&indices[..]);
+    /// manager.buffer_builder::<u16>(None).build_init(vec);
+    /// # }
+    /// ```
+    pub fn build_init(self, init_data: impl AsRef<[T]>) -> BufferHandle {
+        let init_data = init_data.as_ref();
+
+        debug_assert!(
+            self.frames_in_flight == 1,
+            "frames_in_flight is only supported by BufferBuilder::build"
+        );
+
+        if cfg!(debug_assertions) && self.check_nan {
+            check_nan(self.label, bytemuck::cast_slice(init_data));
+        }
+
+        let mut buffer = Buffer::new_init(
             self.manager,
             self.label,
             self.usages,
             init_data,
             self.vertex_format,
         );
+        buffer.check_nan = self.check_nan;
 
         self.manager.add_buffer(buffer)
     }
 }
 
+/// A buffer that is mapped into cpu memory, created by [`BufferBuilder::build_mapped`]
+///
+/// Derefs to a slice of the buffer's elements, the buffer is unmapped when this is dropped.
+pub struct MappedBuffer<'a, T: BufferContents> {
+    buffer: &'a RawBuffer,
+    view: Option<BufferViewMut<'a>>,
+    len: usize,
+    __buffer_type: PhantomData<T>,
+}
+
+impl<'a, T: BufferContents> Deref for MappedBuffer<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        let bytes = self.len * std::mem::size_of::<T>();
+        bytemuck::cast_slice(&self.view.as_ref().unwrap()[.. bytes])
+    }
+}
+
+impl<'a, T: BufferContents> DerefMut for MappedBuffer<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let bytes = self.len * std::mem::size_of::<T>();
+        bytemuck::cast_slice_mut(&mut self.view.as_mut().unwrap()[.. bytes])
+    }
+}
+
+impl<'a, T: BufferContents> Drop for MappedBuffer<'a, T> {
+    fn drop(&mut self) {
+        // The view has to be dropped before the buffer can be unmapped
+        drop(self.view.take());
+        self.buffer.unmap();
+    }
+}
+
 impl<'a, T: Vertex> BufferBuilder<'a, T> {
     pub fn vertex(mut self) -> Self {
         self.usages |= BufferUsages::VERTEX;
@@ -245,3 +706,64 @@ impl<'a> BufferBuilder<'a, u32> {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manager::RenderManager;
+
+    #[test]
+    fn append_goes_after_the_last_element() {
+        // Three u32s in a buffer padded out to 16 bytes like build_mapped does
+        assert_eq!(AppendLayout::new(3, 4, 2), AppendLayout {
+            offset: 12,
+            size: 20,
+            len: 5,
+        });
+        assert_eq!(AppendLayout::new(0, 8, 4), AppendLayout {
+            offset: 0,
+            size: 32,
+            len: 4,
+        });
+    }
+
+    #[test]
+    fn append_to_a_padded_buffer() {
+        let Some(mut manager) = RenderManager::new_headless() else {
+            return;
+        };
+
+        let (buffer, mut mapped) = manager
+            .buffer_builder::<u32>(None)
+            .copy_src()
+            .copy_dst()
+            .build_mapped(3);
+        mapped.copy_from_slice(&[1, 2, 3]);
+        drop(mapped);
+
+        manager.append_to_buffer(buffer, &[4u32, 5]);
+
+        assert_eq!(manager.read_buffer::<u32>(buffer), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn zeroed_buffers_read_back_zeros() {
+        let Some(mut manager) = RenderManager::new_headless() else {
+            return;
+        };
+
+        // Six bytes isn't a valid size for a buffer mapped at creation without padding
+        let odd = manager
+            .buffer_builder::<u16>(None)
+            .copy_src()
+            .build_zeroed(3);
+        let storage = manager
+            .buffer_builder::<[u32; 2]>(None)
+            .storage()
+            .copy_src()
+            .build_zeroed(4);
+
+        assert_eq!(manager.read_buffer::<u16>(odd), [0; 3]);
+        assert_eq!(manager.read_buffer::<[u32; 2]>(storage), [[0; 2]; 4]);
+    }
+}
@@ -0,0 +1,302 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bytemuck::{Pod, Zeroable};
+use petra_math::{GpuMat4, Mat4, Quat, Vec2, Vec3, Vec4};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+use crate::manager::RenderManager;
+
+/// A perspective camera looking from `position` at `target`
+///
+/// All the matrices it produces are in wgpu's clip space and can be sent straight to the gpu.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub position: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    pub fov_radians: f32,
+    pub aspect_ratio: f32,
+    pub near_clip: f32,
+    pub far_clip: f32,
+}
+
+impl Camera {
+    pub fn new(position: Vec3, target: Vec3, fov_radians: f32, aspect_ratio: f32) -> Camera {
+        Camera {
+            position,
+            target,
+            up: Vec3::Y,
+            fov_radians,
+            aspect_ratio,
+            near_clip: 0.1,
+            far_clip: 100.0,
+        }
+    }
+
+    /// Creates a camera with its aspect ratio matching the current surface size
+    pub fn for_surface(
+        manager: &RenderManager,
+        position: Vec3,
+        target: Vec3,
+        fov_radians: f32,
+    ) -> Camera {
+        let mut camera = Camera::new(position, target, fov_radians, 1.0);
+        camera.update_aspect_ratio(manager);
+        camera
+    }
+
+    /// Recalculates the aspect ratio from the current surface size
+    ///
+    /// This should be called after the surface is resized
+    pub fn update_aspect_ratio(&mut self, manager: &RenderManager) {
+        let size = manager.surface_size();
+
+        // A minimized window has a size of 0, keep the old aspect ratio instead of dividing by 0
+        if size.width != 0 && size.height != 0 {
+            self.aspect_ratio = size.width as f32 / size.height as f32;
+        }
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at(self.position, self.target, self.up)
+    }
+
+    pub fn projection(&self) -> Mat4 {
+        Mat4::perspective_projection(
+            self.fov_radians,
+            self.aspect_ratio,
+            self.near_clip,
+            self.far_clip,
+        ) * Mat4::OPENGL_TO_WGPU
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.view() * self.projection()
+    }
+
+    pub fn uniform(&self) -> CameraUniform {
+        let view = self.view();
+        let projection = self.projection();
+
+        CameraUniform {
+            view: view.for_shader(),
+            projection: projection.for_shader(),
+            view_projection: (view * projection).for_shader(),
+            position: Vec4::from_xyz(self.position, 1.0),
+        }
+    }
+}
+
+/// The data from a [`Camera`] laid out to be used in a uniform buffer
+///
+/// Matches this wgsl struct:
+/// ```wgsl
+/// struct Camera {
+///     view: mat4x4<f32>,
+///     projection: mat4x4<f32>,
+///     view_projection: mat4x4<f32>,
+///     position: vec4<f32>,
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C, align(8))]
+pub struct CameraUniform {
+    pub view: GpuMat4,
+    pub projection: GpuMat4,
+    pub view_projection: GpuMat4,
+    pub position: Vec4,
+}
+
+/// A controller that orbits a [`Camera`] around a target point
+///
+/// Dragging with the left mouse button rotates around the target and scrolling zooms in and out.
+/// Pass window events to [`OrbitCamera::handle_event`] and then call [`OrbitCamera::apply`]
+/// before building the camera uniform.
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub distance: f32,
+    /// Rotation around the y axis in radians
+    pub yaw: f32,
+    /// Rotation above or below the target in radians, kept just short of straight up or down
+    pub pitch: f32,
+    /// Radians rotated per pixel dragged
+    pub rotate_speed: f32,
+    /// Fraction of the distance zoomed per line scrolled
+    pub zoom_speed: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    dragging: bool,
+    last_cursor: Option<Vec2>,
+}
+
+impl OrbitCamera {
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+    pub fn new(target: Vec3, distance: f32) -> OrbitCamera {
+        OrbitCamera {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            rotate_speed: 0.01,
+            zoom_speed: 0.1,
+            min_distance: 0.1,
+            max_distance: f32::INFINITY,
+            dragging: false,
+            last_cursor: None,
+        }
+    }
+
+    /// Updates the controller from a window event
+    ///
+    /// Returns whether the event was used by the controller
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.dragging = *state == ElementState::Pressed;
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = Vec2::new(position.x as f32, position.y as f32);
+                let last = self.last_cursor.replace(cursor);
+
+                match last {
+                    Some(last) if self.dragging => {
+                        self.drag(cursor - last);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.last_cursor = None;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    // Treat roughly one line as 20 pixels like most platforms do
+                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 20.0,
+                };
+                self.zoom(lines);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Rotates around the target by a cursor movement in pixels
+    ///
+    /// Moving right orbits to the right and moving down orbits upwards
+    pub fn drag(&mut self, delta: Vec2) {
+        self.yaw -= delta.x() * self.rotate_speed;
+        self.pitch =
+            (self.pitch + delta.y() * self.rotate_speed).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    }
+
+    /// Moves towards the target, negative values move away from it
+    pub fn zoom(&mut self, lines: f32) {
+        self.distance = (self.distance * (1.0 - lines * self.zoom_speed))
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    /// The rotation of the camera around the target
+    pub fn orientation(&self) -> Quat {
+        Quat::from_axis_angle(Vec3::Y, self.yaw) * Quat::from_axis_angle(Vec3::X, -self.pitch)
+    }
+
+    pub fn position(&self) -> Vec3 {
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+
+        self.target + self.distance * Vec3::new(pitch_cos * yaw_sin, pitch_sin, pitch_cos * yaw_cos)
+    }
+
+    /// Moves `camera` to look at the target from the orbit's position
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.position = self.position();
+        camera.target = self.target;
+        camera.up = Vec3::Y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::{
+        dpi::PhysicalPosition,
+        event::{DeviceId, ModifiersState, TouchPhase},
+    };
+
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).magnitude() <= 1e-5, "{a} isn't close to {b}");
+    }
+
+    #[test]
+    fn orbit_starts_in_front_of_target() {
+        let orbit = OrbitCamera::new(Vec3::new(1.0, 2.0, 3.0), 5.0);
+        assert_close(orbit.position(), Vec3::new(1.0, 2.0, 8.0));
+    }
+
+    #[test]
+    fn orbit_orientation_matches_position() {
+        let mut orbit = OrbitCamera::new(Vec3::new(1.0, 2.0, 3.0), 5.0);
+        orbit.drag(Vec2::new(-40.0, 25.0));
+
+        let offset = orbit.orientation().rotate_vec3(Vec3::Z) * orbit.distance;
+        assert_close(orbit.target + offset, orbit.position());
+    }
+
+    #[test]
+    fn orbit_pitch_and_distance_are_clamped() {
+        let mut orbit = OrbitCamera::new(Vec3::ZERO, 1.0);
+
+        orbit.drag(Vec2::new(0.0, 10_000.0));
+        assert!(orbit.pitch < FRAC_PI_2);
+        assert!(orbit.position().y() > 0.99);
+
+        orbit.zoom(100.0);
+        assert_eq!(orbit.distance, orbit.min_distance);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn orbit_handles_mouse_events() {
+        // SAFETY: the id is never passed to winit
+        let device_id = unsafe { DeviceId::dummy() };
+        let cursor = |x: f64| WindowEvent::CursorMoved {
+            device_id,
+            position: PhysicalPosition::new(x, 0.0),
+            modifiers: ModifiersState::empty(),
+        };
+        let mut orbit = OrbitCamera::new(Vec3::ZERO, 5.0);
+
+        // Moving without holding the button doesn't rotate
+        assert!(!orbit.handle_event(&cursor(0.0)));
+        assert!(!orbit.handle_event(&cursor(10.0)));
+        assert_eq!(orbit.yaw, 0.0);
+
+        assert!(orbit.handle_event(&WindowEvent::MouseInput {
+            device_id,
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+            modifiers: ModifiersState::empty(),
+        }));
+        assert!(orbit.handle_event(&cursor(20.0)));
+        assert_eq!(orbit.yaw, -10.0 * orbit.rotate_speed);
+
+        assert!(orbit.handle_event(&WindowEvent::MouseWheel {
+            device_id,
+            delta: MouseScrollDelta::LineDelta(0.0, 1.0),
+            phase: TouchPhase::Moved,
+            modifiers: ModifiersState::empty(),
+        }));
+        assert_eq!(orbit.distance, 4.5);
+    }
+}
@@ -4,6 +4,11 @@ use crate::{compute_pipeline::ComputePipelineHandle, handle::Handle, manager::Re
 
 pub type ComputePassHandle = Handle<ComputePass>;
 
+/// A list of compute pipelines dispatched one after another
+///
+/// Each pipeline's dispatch sees every storage buffer and texture write made by the pipelines
+/// before it in the pass. wgpu tracks resource usage per dispatch and inserts the barriers
+/// itself, so dependent phases like the passes of a prefix sum can go in the same pass.
 pub struct ComputePass {
     pub(crate) name: Option<String>,
     pub(crate) pipelines: Vec<ComputePipelineHandle>,
@@ -24,6 +29,9 @@ impl<'a> ComputePassBuilder<'a> {
         }
     }
 
+    /// Adds a pipeline to be dispatched after the ones already added
+    ///
+    /// It's guaranteed to see the writes of the earlier pipelines, see [`ComputePass`].
     pub fn add_pipeline(mut self, pipeline: ComputePipelineHandle) -> Self {
         self.pipelines.push(pipeline);
         self
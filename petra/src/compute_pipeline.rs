@@ -18,14 +18,27 @@ pub struct ComputePipeline {
     pipeline: RawComputePipeline,
     pub(crate) bind_groups: Vec<BindGroupHandle>,
     pub(crate) work_groups: [u32; 3],
+    workgroup_size: [u32; 3],
+    pub(crate) name: Option<String>,
 }
 
 impl ComputePipeline {
     pub fn inner(&self) -> &RawComputePipeline {
         &self.pipeline
     }
+
+    /// The `@workgroup_size` of the pipeline's entry point, the number of invocations in each
+    /// of the work groups dispatched
+    pub fn workgroup_size(&self) -> [u32; 3] {
+        self.workgroup_size
+    }
 }
 
+/// Builds a [`ComputePipeline`] from a shader entry point and its bind groups
+///
+/// Pipeline overridable constants (WGSL `override` declarations) can't be set yet,
+/// the wgpu version used has no `PipelineCompilationOptions` to pass them through.<br>
+/// Shaders using them fail to build, use a `const` or a uniform instead.
 pub struct ComputePipelineBuilder<'a> {
     name: Label<'a>,
     manager: &'a mut RenderManager,
@@ -85,27 +98,56 @@ impl<'a> ComputePipelineBuilder<'a> {
                     push_constant_ranges: &[],
                 });
 
+        let shader = self
+            .manager
+            .get_shader(
+                self.shader
+                    .expect("No shader proveded in ComputePipelineBuilder"),
+            )
+            .expect("Invalid ShaderHandle passed to ComputePipelineBuilder");
+        let entry_point = self.entry_point.unwrap();
+        let workgroup_size = shader.workgroup_size(entry_point).unwrap_or_else(|| {
+            panic!(
+                "Compute pipeline {:?} uses {entry_point} which isn't a compute entry point of \
+                 its shader",
+                self.name
+            )
+        });
+
+        // Catch missing groups here, wgpu only reports them as a mismatched pipeline layout
+        if let Some(groups) = shader.entry_point_groups(entry_point) {
+            let missing = groups
+                .iter()
+                .filter(|group| **group as usize >= self.bind_groups.len())
+                .collect::<Vec<_>>();
+
+            assert!(
+                missing.is_empty(),
+                "Compute pipeline {:?} is missing bind groups {missing:?} used by {entry_point}, \
+                 only {} bind groups were added",
+                self.name,
+                self.bind_groups.len()
+            );
+        }
+
+        let pipeline = self
+            .manager
+            .device
+            .create_compute_pipeline(&ComputePipelineDescriptor {
+                label: self.name,
+                layout: Some(&pipeline_layout),
+                module: &shader.module,
+                entry_point,
+            });
+
         self.manager.add_compute_pipeline(ComputePipeline {
-            pipeline: self
-                .manager
-                .device
-                .create_compute_pipeline(&ComputePipelineDescriptor {
-                    label: self.name,
-                    layout: Some(&pipeline_layout),
-                    module: &self
-                        .manager
-                        .get_shader(
-                            self.shader
-                                .expect("No shader proveded in ComputePipelineBuilder"),
-                        )
-                        .expect("Invalid ShaderHandle passed to ComputePipelineBuilder")
-                        .0,
-                    entry_point: self.entry_point.unwrap(),
-                }),
+            pipeline,
             bind_groups: self.bind_groups,
             work_groups: self
                 .work_groups
                 .expect("No work groups defined for a ComputePipelineBuilder"),
+            workgroup_size,
+            name: self.name.map(str::to_owned),
         })
     }
 }
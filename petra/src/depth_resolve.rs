@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use wgpu::{
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingResource,
+    BindingType,
+    CommandEncoder,
+    CompareFunction,
+    DepthStencilState,
+    Device,
+    FragmentState,
+    LoadOp,
+    MultisampleState,
+    Operations,
+    PipelineLayout,
+    PipelineLayoutDescriptor,
+    PrimitiveState,
+    RenderPassDepthStencilAttachment,
+    RenderPassDescriptor,
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    ShaderModule,
+    ShaderModuleDescriptor,
+    ShaderSource,
+    ShaderStages,
+    TextureFormat,
+    TextureSampleType,
+    TextureView,
+    TextureViewDimension,
+    VertexState,
+};
+
+/// The pipelines that copy a multisampled depth texture into a single sampled one
+///
+/// wgpu has no depth resolve attachment so this is done with a fullscreen draw that writes
+/// the first sample of each pixel to `frag_depth`.<br>
+/// Pipelines depend on the depth format so they're created when a render pass that resolves
+/// that format is built, since passes are run without mutable access to the manager.
+pub(crate) struct DepthResolveCache {
+    shader: ShaderModule,
+    bind_group_layout: BindGroupLayout,
+    pipeline_layout: PipelineLayout,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl DepthResolveCache {
+    pub(crate) fn new(device: &Device) -> DepthResolveCache {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Depth Resolve Shader"),
+            source: ShaderSource::Wgsl(include_str!("depth_resolve.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Depth Resolve Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Depth,
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: true,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Depth Resolve Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        DepthResolveCache {
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Creates the pipeline for resolving into `format` if it doesn't exist yet
+    pub(crate) fn prepare(&mut self, device: &Device, format: TextureFormat) {
+        self.pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Depth Resolve Pipeline"),
+                layout: Some(&self.pipeline_layout),
+                vertex: VertexState {
+                    module: &self.shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                primitive: PrimitiveState::default(),
+                depth_stencil: Some(DepthStencilState {
+                    format,
+                    depth_write_enabled: true,
+                    depth_compare: CompareFunction::Always,
+                    stencil: Default::default(),
+                    bias: Default::default(),
+                }),
+                multisample: MultisampleState::default(),
+                fragment: Some(FragmentState {
+                    module: &self.shader,
+                    entry_point: "fs_main",
+                    targets: &[],
+                }),
+                multiview: None,
+            })
+        });
+    }
+
+    /// Records a draw that copies the first sample of each pixel of `source` into `target`
+    ///
+    /// `source` has to be a depth only view of a multisampled texture and [`prepare`](Self::prepare)
+    /// has to have been called with `target_format`.
+    pub(crate) fn resolve(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &TextureView,
+        target: &TextureView,
+        target_format: TextureFormat,
+    ) {
+        let pipeline = self
+            .pipelines
+            .get(&target_format)
+            .expect("Depth resolve pipeline used before it was prepared");
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Depth Resolve Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(source),
+            }],
+        });
+
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Depth Resolve Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: target,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(0.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0 .. 3, 0 .. 1);
+    }
+}
@@ -22,6 +22,16 @@ impl<T> Registry<T> {
     pub(crate) fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
         self.data.get_mut(handle.0)
     }
+
+    /// The handles of every value that matches `predicate`, in the order they were added
+    pub(crate) fn handles_where(&self, predicate: impl Fn(&T) -> bool) -> Vec<Handle<T>> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, val)| predicate(val))
+            .map(|(i, _)| Handle::new(i))
+            .collect()
+    }
 }
 
 impl<T> Default for Registry<T> {
@@ -70,3 +80,19 @@ impl<T> PartialEq for Handle<T> {
 }
 
 impl<T> Eq for Handle<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_where_finds_matching_values() {
+        let mut registry = Registry::new();
+        let one = registry.add(1);
+        registry.add(2);
+        let three = registry.add(3);
+
+        assert!(registry.handles_where(|v| v % 2 == 1) == [one, three]);
+        assert!(registry.handles_where(|v| *v > 3).is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Error, ErrorKind, Seek},
+    path::Path,
+};
+
+use bytemuck::Zeroable;
+use image::{io::Reader, DynamicImage, ImageError};
+use wgpu::{Label, TextureFormat};
+
+use crate::{
+    manager::RenderManager,
+    texture::{TextureContents, TextureHandle},
+};
+
+impl RenderManager {
+    /// Loads a PNG or JPEG file into a 2d texture of `T`s sized to the image
+    ///
+    /// `T` has to be an 8 or 16 bit normalized format with as many channels as the image,
+    /// like `Srgb<Norm<[u8; 4]>>` for color textures.<br>
+    /// RGB images get an opaque alpha channel and palettes are expanded to RGB(A).
+    /// The texture is created with `.texture()` and `.copy_dst()` like
+    /// [`build_init`](crate::texture::TextureBuilder::build_init).
+    ///
+    /// Files that aren't PNGs or JPEGs return an [`ErrorKind::Unsupported`] error,
+    /// as do `T`s that aren't 8 or 16 bit unorm formats.
+    /// Images that don't match `T`'s channels or bit depth return an [`ErrorKind::InvalidData`]
+    /// error.
+    pub fn load_texture_file<T: TextureContents>(
+        &mut self,
+        path: impl AsRef<Path>,
+        label: Label<'_>,
+    ) -> std::io::Result<TextureHandle> {
+        let (width, height, pixels) = decode_image(BufReader::new(File::open(path)?), T::FORMAT)?;
+
+        let mut data = vec![T::Data::zeroed(); (width * height) as usize];
+        bytemuck::cast_slice_mut(&mut data).copy_from_slice(&pixels);
+
+        Ok(self
+            .texture_builder::<T>(label)
+            .size_2d(width, height)
+            .build_init(&data))
+    }
+}
+
+/// Decodes an image into its width, height, and pixels in the layout of a `format` texture
+fn decode_image(
+    reader: impl BufRead + Seek,
+    format: TextureFormat,
+) -> std::io::Result<(u32, u32, Vec<u8>)> {
+    let image = Reader::new(reader)
+        .with_guessed_format()?
+        .decode()
+        .map_err(image_error)?;
+
+    let (width, height) = (image.width(), image.height());
+    Ok((width, height, convert_pixels(image, format)?))
+}
+
+/// Turns an `image` error into an io error, keeping unsupported formats distinguishable
+fn image_error(error: ImageError) -> Error {
+    match error {
+        ImageError::IoError(error) => error,
+        ImageError::Unsupported(_) => Error::new(ErrorKind::Unsupported, error),
+        error => Error::new(ErrorKind::InvalidData, error),
+    }
+}
+
+/// Converts a decoded image into the layout of a `format` texture
+fn convert_pixels(image: DynamicImage, format: TextureFormat) -> std::io::Result<Vec<u8>> {
+    let (channels, depth, bgra) = match format {
+        TextureFormat::R8Unorm => (1, 8, false),
+        TextureFormat::Rg8Unorm => (2, 8, false),
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => (4, 8, false),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => (4, 8, true),
+        TextureFormat::R16Unorm => (1, 16, false),
+        TextureFormat::Rg16Unorm => (2, 16, false),
+        TextureFormat::Rgba16Unorm => (4, 16, false),
+        _ =>
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "Can't load an image into a {format:?} texture, use an 8 or 16 bit unorm \
+                     format"
+                ),
+            )),
+    };
+
+    let color = image.color();
+    let image_channels = color.channel_count();
+    let image_depth = color.bits_per_pixel() / image_channels as u16;
+
+    if image_depth != depth {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Image has {image_depth} bit channels but {format:?} textures have {depth} bit \
+                 channels"
+            ),
+        ));
+    }
+
+    // There are no 3 channel texture formats, so RGB images get an opaque alpha
+    if image_channels != channels && !(image_channels == 3 && channels == 4) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Image has {image_channels} channels but {format:?} textures have {channels}"),
+        ));
+    }
+
+    let mut pixels = match (channels, depth) {
+        (1, 8) => image.into_luma8().into_raw(),
+        (2, 8) => image.into_luma_alpha8().into_raw(),
+        (4, 8) => image.into_rgba8().into_raw(),
+        (1, 16) => bytemuck::cast_slice(&image.into_luma16()).to_vec(),
+        (2, 16) => bytemuck::cast_slice(&image.into_luma_alpha16()).to_vec(),
+        _ => bytemuck::cast_slice(&image.into_rgba16()).to_vec(),
+    };
+
+    if bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use image::{
+        codecs::{jpeg::JpegEncoder, png::PngEncoder},
+        ColorType,
+        ImageEncoder,
+    };
+    use wgpu::TextureFormat;
+
+    use super::decode_image;
+
+    fn encode_png(width: u32, height: u32, color: ColorType, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        PngEncoder::new(&mut bytes)
+            .write_image(data, width, height, color)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip_rgba() {
+        let data: Vec<u8> = (0 .. 3 * 2 * 4).collect();
+        let png = encode_png(3, 2, ColorType::Rgba8, &data);
+
+        let (width, height, pixels) =
+            decode_image(Cursor::new(png), TextureFormat::Rgba8Unorm).unwrap();
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(pixels, data);
+    }
+
+    #[test]
+    fn rgb_gets_opaque_alpha() {
+        let png = encode_png(2, 1, ColorType::Rgb8, &[1, 2, 3, 4, 5, 6]);
+
+        let (width, height, pixels) =
+            decode_image(Cursor::new(png), TextureFormat::Bgra8UnormSrgb).unwrap();
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(pixels, [3, 2, 1, 0xFF, 6, 5, 4, 0xFF]);
+    }
+
+    #[test]
+    fn sixteen_bit_is_native_endian() {
+        let png = encode_png(1, 1, ColorType::L16, &0x1234u16.to_ne_bytes());
+
+        let (_, _, pixels) = decode_image(Cursor::new(png), TextureFormat::R16Unorm).unwrap();
+        assert_eq!(pixels, 0x1234u16.to_ne_bytes());
+    }
+
+    #[test]
+    fn jpeg_round_trip() {
+        let data = [200, 100, 50].repeat(8 * 4);
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, 100)
+            .write_image(&data, 8, 4, ColorType::Rgb8)
+            .unwrap();
+
+        let (width, height, pixels) =
+            decode_image(Cursor::new(jpeg), TextureFormat::Rgba8UnormSrgb).unwrap();
+        assert_eq!((width, height), (8, 4));
+        // JPEG is lossy, so only check the colors are close
+        for pixel in pixels.chunks_exact(4) {
+            for (channel, expected) in pixel.iter().zip([200, 100, 50, 0xFF]) {
+                assert!(channel.abs_diff(expected) <= 2, "{pixel:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn mismatched_channels_error() {
+        let png = encode_png(1, 1, ColorType::L8, &[0]);
+
+        let error = decode_image(Cursor::new(png), TextureFormat::Rgba8Unorm).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn unknown_format_is_unsupported() {
+        let error =
+            decode_image(Cursor::new(b"not an image"), TextureFormat::Rgba8Unorm).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
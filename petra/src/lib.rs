@@ -1,9 +1,21 @@
+//! A wgpu renderer built around a [`RenderManager`](manager::RenderManager) that owns every
+//! buffer, texture, pipeline, and pass and hands out handles to them
+//!
+//! This is the workspace's only rendering crate, new examples and features belong here.
+
 pub mod bind_group;
+mod blit;
 pub mod buffer;
+pub mod camera;
 pub mod compute_pass;
 pub mod compute_pipeline;
+mod depth_resolve;
 pub mod handle;
+#[cfg(feature = "image")]
+pub mod image;
 pub mod manager;
+#[cfg(feature = "obj")]
+pub mod obj;
 pub mod render_pass;
 pub mod render_pipeline;
 pub mod sampler;
@@ -1,8 +1,29 @@
-use std::{fs::OpenOptions, io::Read, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Display, Formatter},
+    fs::OpenOptions,
+    io::Read,
+    num::NonZeroU32,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
+use petra_math::Vec2;
+use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 pub use wgpu::SurfaceError;
 use wgpu::{
+    Adapter,
+    AdapterInfo,
     Backends,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BufferUsages,
+    Color,
     CommandEncoder,
     CommandEncoderDescriptor,
     ComputePassDescriptor,
@@ -14,16 +35,26 @@ use wgpu::{
     InstanceDescriptor,
     Label,
     Limits,
+    LoadOp,
+    Maintain,
+    Operations,
     PowerPreference,
+    PresentMode,
     Queue,
+    RenderPass as RawRenderPass,
     RenderPassColorAttachment,
     RenderPassDepthStencilAttachment,
     RenderPassDescriptor,
     RequestAdapterOptions,
+    RequestDeviceError,
     ShaderModuleDescriptor,
     ShaderSource,
+    SubmissionIndex,
     Surface,
     SurfaceConfiguration,
+    SurfaceTexture,
+    TextureFormat,
+    TextureSampleType,
     TextureUsages,
     TextureView,
     TextureViewDescriptor,
@@ -31,21 +62,122 @@ use wgpu::{
 use winit::{dpi::PhysicalSize, window::Window};
 
 use crate::{
-    bind_group::{BindGroup, BindGroupBuilder},
+    bind_group::{BindGroup, BindGroupBuilder, BindGroupHandle, BindingKind},
+    blit::BlitCache,
     buffer::{Buffer, BufferBuilder, BufferContents, BufferHandle},
     compute_pass::{ComputePass, ComputePassBuilder, ComputePassHandle},
-    compute_pipeline::{ComputePipeline, ComputePipelineBuilder},
+    compute_pipeline::{ComputePipeline, ComputePipelineBuilder, ComputePipelineHandle},
+    depth_resolve::DepthResolveCache,
     handle::{Handle, Registry},
-    render_pass::{RenderPass, RenderPassBuilder, RenderPassHandle},
-    render_pipeline::{PipelineHandle, RenderPipeline, RenderPipelineBuilder},
-    sampler::{TextureSampler, TextureSamplerBuilder},
+    render_pass::{DepthAttachment, RenderPass, RenderPassBuilder, RenderPassHandle},
+    render_pipeline::{
+        PipelineHandle,
+        RenderPipeline,
+        RenderPipelineBuilder,
+        DRAW_INDEXED_INDIRECT_SIZE,
+        DRAW_INDIRECT_SIZE,
+    },
+    sampler::{TextureSampleHandle, TextureSampler, TextureSamplerBuilder},
     shader::{Shader, ShaderHandle},
-    texture::{Texture, TextureBuilder, TextureContents, FRAMEBUFFER},
+    texture::{Texture, TextureBuilder, TextureContents, TextureHandle, FRAMEBUFFER},
 };
 
+/// Options for creating a [`RenderManager`]
+#[derive(Clone, Debug)]
+pub struct RenderManagerDescriptor {
+    /// The backends an adapter can be picked from, defaults to `Backends::PRIMARY`
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    /// The shader compiler used on DX12, defaults to FXC
+    ///
+    /// `Dx12Compiler::Dxc` compiles faster and produces better shaders but needs `dxil.dll` and
+    /// `dxcompiler.dll` shipped next to the executable or at the given paths.<br>
+    /// If they can't be loaded wgpu logs an error and falls back to FXC.
+    pub dx12_shader_compiler: Dx12Compiler,
+    /// Optional features the device is created with, defaults to none
+    ///
+    /// Features like `Features::MULTIVIEW` or `Features::PUSH_CONSTANTS` have to be requested
+    /// here before anything using them can be created.
+    pub features: Features,
+    /// The limits the device is created with, defaults to `Limits::default()`
+    /// or `Limits::downlevel_webgl2_defaults()` on wasm
+    ///
+    /// Raise these to use more than the defaults allow, like larger textures.
+    pub limits: Limits,
+}
+
+impl Default for RenderManagerDescriptor {
+    fn default() -> Self {
+        RenderManagerDescriptor {
+            backends: Backends::PRIMARY,
+            power_preference: PowerPreference::HighPerformance,
+            dx12_shader_compiler: Dx12Compiler::Fxc,
+            features: Features::empty(),
+            limits: if cfg!(target_arch = "wasm32") {
+                Limits::downlevel_webgl2_defaults()
+            } else {
+                Limits::default()
+            },
+        }
+    }
+}
+
+/// Why a [`RenderManager`] couldn't be created
+#[derive(Debug)]
+pub enum RenderManagerError {
+    /// No adapter for the given backends can draw to the window
+    NoAdapter(Backends),
+    /// The adapter doesn't support some of the features in [`RenderManagerDescriptor::features`]
+    MissingFeatures(AdapterInfo, Features),
+    /// The adapter doesn't support some of the limits in [`RenderManagerDescriptor::limits`],
+    /// each described by its name, the requested value, and the supported value
+    ExceededLimits(AdapterInfo, Vec<String>),
+    /// The adapter failed to create a device
+    RequestDevice(AdapterInfo, RequestDeviceError),
+}
+
+impl Display for RenderManagerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderManagerError::NoAdapter(backends) => write!(
+                f,
+                "No graphics adapter that can draw to the window was found for backends \
+                 {backends:?}"
+            ),
+            RenderManagerError::MissingFeatures(info, features) => write!(
+                f,
+                "The adapter {:?} ({:?}) doesn't support the requested features {features:?}",
+                info.name, info.backend
+            ),
+            RenderManagerError::ExceededLimits(info, limits) => write!(
+                f,
+                "The adapter {:?} ({:?}) doesn't support the requested limits {}",
+                info.name,
+                info.backend,
+                limits.join(", ")
+            ),
+            RenderManagerError::RequestDevice(info, e) => write!(
+                f,
+                "Failed to create a device on the adapter {:?} ({:?}): {e}",
+                info.name, info.backend
+            ),
+        }
+    }
+}
+
+impl Error for RenderManagerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenderManagerError::RequestDevice(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 pub struct RenderManager {
-    pub window: Window,
-    pub(crate) surface: Surface,
+    /// Only `None` for the headless managers used in tests
+    pub(crate) surface: Option<Surface>,
+    adapter: Adapter,
     pub(crate) device: Arc<Device>,
     pub(crate) queue: Arc<Queue>,
     pub(crate) config: SurfaceConfiguration,
@@ -60,6 +192,18 @@ pub struct RenderManager {
     textures: Registry<Texture>,
     bind_groups: Registry<BindGroup>,
     samplers: Registry<TextureSampler>,
+    bind_group_layouts: HashMap<Vec<BindGroupLayoutEntry>, Arc<BindGroupLayout>>,
+    resize_callbacks: Vec<Box<dyn FnMut(u32, u32) + Send + Sync>>,
+    /// Maps shader sources to the shader compiled from them
+    shader_cache: HashMap<String, ShaderHandle>,
+    /// Created the first time anything blits
+    blit_cache: Option<BlitCache>,
+    /// Created when the first render pass that resolves depth is built
+    depth_resolve_cache: Option<DepthResolveCache>,
+    /// Whether a pass has written to this frame's surface texture yet
+    framebuffer_initialized: AtomicBool,
+    // Declared last so the window is dropped after the surface
+    window: Option<Window>,
 }
 
 macro_rules! add_resource_methods {
@@ -87,37 +231,74 @@ impl RenderManager {
     }
 
     pub async fn new(window: Window) -> Self {
-        let instance = Instance::new(InstanceDescriptor {
-            backends: Backends::PRIMARY,
-            dx12_shader_compiler: Dx12Compiler::default(),
-        });
+        Self::new_with_descriptor(window, RenderManagerDescriptor::default())
+            .await
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
 
-        let surface = unsafe { instance.create_surface(&window).unwrap() };
+    /// Creates a manager that only uses the given backends, like `Backends::GL` or `Backends::DX12`
+    ///
+    /// Useful for checking whether a bug is specific to one driver.
+    pub async fn new_with_backend(window: Window, backends: Backends) -> Self {
+        Self::new_with_descriptor(window, RenderManagerDescriptor {
+            backends,
+            ..Default::default()
+        })
+        .await
+        .unwrap_or_else(|e| panic!("{e}"))
+    }
 
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .unwrap();
+    /// Creates a manager with the backend and adapter options in `descriptor`
+    ///
+    /// Returns an error if no adapter can draw to the window or the adapter doesn't support the
+    /// requested features and limits.
+    pub async fn new_with_descriptor(
+        window: Window,
+        descriptor: RenderManagerDescriptor,
+    ) -> Result<Self, RenderManagerError> {
+        // Safety: the window is kept alive by the manager for as long as the surface
+        let mut manager = unsafe {
+            Self::new_with_surface_descriptor(&window, window.inner_size(), descriptor).await?
+        };
+        manager.window = Some(window);
+        Ok(manager)
+    }
 
-        let (device, queue) = adapter
-            .request_device(
-                &DeviceDescriptor {
-                    label: Some("Main device"),
-                    features: Features::empty(),
-                    limits: if cfg!(target_arch = "wasm32") {
-                        Limits::downlevel_webgl2_defaults()
-                    } else {
-                        Limits::default()
-                    },
-                },
-                None,
-            )
+    /// Creates a manager that renders to a window not managed by winit
+    ///
+    /// `size` is the initial size of the window's surface,
+    /// call [`RenderManager::resize`] whenever the window is resized.
+    ///
+    /// # Safety
+    /// `handle` must stay valid until the returned manager is dropped
+    pub async unsafe fn new_with_surface<W: HasRawWindowHandle + HasRawDisplayHandle>(
+        handle: &W,
+        size: PhysicalSize<u32>,
+    ) -> Self {
+        Self::new_with_surface_descriptor(handle, size, RenderManagerDescriptor::default())
             .await
-            .unwrap();
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// [`RenderManager::new_with_surface`] with a descriptor
+    ///
+    /// Returns the same errors as [`RenderManager::new_with_descriptor`].
+    ///
+    /// # Safety
+    /// `handle` must stay valid until the returned manager is dropped
+    pub async unsafe fn new_with_surface_descriptor<W: HasRawWindowHandle + HasRawDisplayHandle>(
+        handle: &W,
+        size: PhysicalSize<u32>,
+        descriptor: RenderManagerDescriptor,
+    ) -> Result<Self, RenderManagerError> {
+        let instance = Instance::new(InstanceDescriptor {
+            backends: descriptor.backends,
+            dx12_shader_compiler: descriptor.dx12_shader_compiler.clone(),
+        });
+
+        let surface = instance.create_surface(handle).unwrap();
+        let (adapter, device, queue) =
+            Self::request_device(&instance, Some(&surface), &descriptor).await?;
 
         let surface_capabilities = surface.get_capabilities(&adapter);
         let surface_format = surface_capabilities
@@ -127,12 +308,11 @@ impl RenderManager {
             .copied()
             .unwrap_or(surface_capabilities.formats[0]);
 
-        let window_size = window.inner_size();
         let config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
-            width: window_size.width,
-            height: window_size.height,
+            width: size.width,
+            height: size.height,
             present_mode: surface_capabilities.present_modes[0],
             alpha_mode: surface_capabilities.alpha_modes[0],
             view_formats: vec![],
@@ -140,13 +320,114 @@ impl RenderManager {
 
         surface.configure(&device, &config);
 
+        Ok(Self::from_parts(
+            Some(surface),
+            adapter,
+            device,
+            queue,
+            config,
+        ))
+    }
+
+    /// Creates a manager with no surface for tests that only need a device
+    ///
+    /// Returns `None` if there's no adapter, so tests can skip on machines without one.
+    #[cfg(test)]
+    pub(crate) fn new_headless() -> Option<Self> {
+        let descriptor = RenderManagerDescriptor {
+            backends: Backends::all(),
+            limits: Limits::downlevel_defaults(),
+            ..Default::default()
+        };
+        let instance = Instance::new(InstanceDescriptor {
+            backends: descriptor.backends,
+            dx12_shader_compiler: descriptor.dx12_shader_compiler.clone(),
+        });
+        let (adapter, device, queue) =
+            pollster::block_on(Self::request_device(&instance, None, &descriptor)).ok()?;
+
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: TextureFormat::Rgba8UnormSrgb,
+            width: 64,
+            height: 64,
+            present_mode: PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+
+        Some(Self::from_parts(None, adapter, device, queue, config))
+    }
+
+    /// Picks an adapter that can draw to `surface` and creates a device meeting `descriptor`
+    async fn request_device(
+        instance: &Instance,
+        surface: Option<&Surface>,
+        descriptor: &RenderManagerDescriptor,
+    ) -> Result<(Adapter, Device, Queue), RenderManagerError> {
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: descriptor.power_preference,
+                force_fallback_adapter: false,
+                compatible_surface: surface,
+            })
+            .await
+            .ok_or(RenderManagerError::NoAdapter(descriptor.backends))?;
+
+        let adapter_info = adapter.get_info();
+
+        let missing_features = descriptor.features - adapter.features();
+        if !missing_features.is_empty() {
+            return Err(RenderManagerError::MissingFeatures(
+                adapter_info,
+                missing_features,
+            ));
+        }
+
+        let mut exceeded_limits = Vec::new();
+        descriptor.limits.check_limits_with_fail_fn(
+            &adapter.limits(),
+            false,
+            |name, requested, allowed| {
+                exceeded_limits.push(format!("{name} of {requested} (supports {allowed})"))
+            },
+        );
+        if !exceeded_limits.is_empty() {
+            return Err(RenderManagerError::ExceededLimits(
+                adapter_info,
+                exceeded_limits,
+            ));
+        }
+
+        let (device, queue) = adapter
+            .request_device(
+                &DeviceDescriptor {
+                    label: Some("Main device"),
+                    features: descriptor.features,
+                    limits: descriptor.limits.clone(),
+                },
+                None,
+            )
+            .await
+            .map_err(|e| RenderManagerError::RequestDevice(adapter_info, e))?;
+
+        Ok((adapter, device, queue))
+    }
+
+    fn from_parts(
+        surface: Option<Surface>,
+        adapter: Adapter,
+        device: Device,
+        queue: Queue,
+        config: SurfaceConfiguration,
+    ) -> Self {
         Self {
-            window,
             surface,
+            adapter,
             device: Arc::new(device),
             queue: Arc::new(queue),
+            size: PhysicalSize::new(config.width, config.height),
             config,
-            size: window_size,
             passes: PassManager::new(),
             render_passes: Registry::new(),
             render_pipelines: Registry::new(),
@@ -157,6 +438,13 @@ impl RenderManager {
             textures: Registry::new(),
             bind_groups: Registry::new(),
             samplers: Registry::new(),
+            bind_group_layouts: HashMap::new(),
+            resize_callbacks: Vec::new(),
+            shader_cache: HashMap::new(),
+            blit_cache: None,
+            depth_resolve_cache: None,
+            framebuffer_initialized: AtomicBool::new(false),
+            window: None,
         }
     }
 
@@ -196,6 +484,45 @@ impl RenderManager {
         TextureBuilder::new(self, label)
     }
 
+    /// Creates a 2d texture and uploads `data` to it in one call
+    ///
+    /// The texture always gets `COPY_DST` and `TEXTURE_BINDING` on top of `usage`.
+    /// Use [`TextureBuilder::build_init`] for textures that aren't 2d.
+    pub fn create_texture_with_data<T: TextureContents>(
+        &mut self,
+        label: Label<'_>,
+        width: u32,
+        height: u32,
+        usage: TextureUsages,
+        data: &[T::Data],
+    ) -> TextureHandle {
+        self.texture_builder::<T>(label)
+            .size_2d(width, height)
+            .usage(usage)
+            .build_init(data)
+    }
+
+    /// Creates a uniform buffer holding `initial` in one call
+    ///
+    /// The buffer gets `UNIFORM` and `COPY_DST` so it can be updated with
+    /// [`RenderManager::write_to_buffer`].
+    /// Use [`BufferBuilder`] for uniforms with multiple frames in flight.
+    pub fn create_uniform<T: BufferContents>(
+        &mut self,
+        label: Label<'_>,
+        initial: T,
+    ) -> BufferHandle {
+        debug_assert!(
+            (std::mem::size_of::<T>() as u64).is_multiple_of(wgpu::MAP_ALIGNMENT),
+            "Data accessed by shaders must have an alignment of 8"
+        );
+
+        self.buffer_builder::<T>(label)
+            .uniform()
+            .copy_dst()
+            .build_init([initial])
+    }
+
     pub fn bind_group_builder<'a>(&'a mut self, label: Label<'a>) -> BindGroupBuilder<'a> {
         BindGroupBuilder::new(self, label)
     }
@@ -223,6 +550,518 @@ impl RenderManager {
                 bind_group.recreate(&self.device, &self.buffers, &self.textures, &self.samplers)
             }
         }
+
+        // Point bind groups at the region that was just written
+        let raw_buffer = self.buffers.get(buffer).unwrap();
+        if raw_buffer.frames_in_flight() > 1 {
+            let offset = raw_buffer.frame_offset();
+
+            for bind_group in (&mut self.bind_groups)
+                .into_iter()
+                .filter(|b| b.depends_buffer(buffer))
+            {
+                bind_group.set_buffer_offset(buffer, offset)
+            }
+        }
+    }
+
+    /// Appends `data` to the end of a buffer, growing it and keeping its old contents
+    ///
+    /// The buffer needs `.copy_src()` and `.copy_dst()`. See [`Buffer::append`].
+    pub fn append_to_buffer<T: BufferContents>(&mut self, buffer: BufferHandle, data: &[T]) {
+        self.buffers
+            .get_mut(buffer)
+            .expect("Invalid buffer handle passed to append_to_buffer")
+            .append(data);
+
+        // The buffer was recreated so anything bound to the old one has to be rebuilt
+        for bind_group in (&mut self.bind_groups)
+            .into_iter()
+            .filter(|b| b.depends_buffer(buffer))
+        {
+            bind_group.recreate(&self.device, &self.buffers, &self.textures, &self.samplers)
+        }
+    }
+
+    /// Copies all of `source` into the start of `target` on the gpu
+    ///
+    /// `source` needs `.copy_src()`, `target` needs `.copy_dst()` and has to be at least as large.
+    pub fn copy_buffer(&mut self, source: BufferHandle, target: BufferHandle) {
+        let size = self
+            .buffers
+            .get(source)
+            .expect("Invalid buffer handle passed to copy_buffer as the source")
+            .inner()
+            .size();
+
+        self.copy_buffer_range(source, 0, target, 0, size);
+    }
+
+    /// Copies `size` bytes from `source_offset` in `source` to `target_offset` in `target`
+    ///
+    /// The offsets and size have to be multiples of 4.
+    pub fn copy_buffer_range(
+        &mut self,
+        source: BufferHandle,
+        source_offset: u64,
+        target: BufferHandle,
+        target_offset: u64,
+        size: u64,
+    ) {
+        let source = self
+            .buffers
+            .get(source)
+            .expect("Invalid buffer handle passed to copy_buffer as the source");
+        let target = self
+            .buffers
+            .get(target)
+            .expect("Invalid buffer handle passed to copy_buffer as the target");
+
+        if cfg!(debug_assertions) {
+            source.validate_usages(BufferUsages::COPY_SRC, "be copied from");
+            target.validate_usages(BufferUsages::COPY_DST, "be copied to");
+
+            assert!(
+                [source_offset, target_offset, size]
+                    .iter()
+                    .all(|n| n.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)),
+                "Buffer copy offsets and sizes must be multiples of {}",
+                wgpu::COPY_BUFFER_ALIGNMENT
+            );
+            for (buffer, offset) in [(source, source_offset), (target, target_offset)] {
+                assert!(
+                    offset + size <= buffer.inner().size(),
+                    "Tried to copy bytes {offset} .. {} of buffer {:?} which is only {} bytes",
+                    offset + size,
+                    buffer.name(),
+                    buffer.inner().size()
+                );
+            }
+        }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Buffer Copy Encoder"),
+            });
+        encoder.copy_buffer_to_buffer(
+            source.inner(),
+            source_offset,
+            target.inner(),
+            target_offset,
+            size,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Reads a buffer back to the cpu, for getting the results of a compute shader
+    ///
+    /// The buffer has to be created with `.map_read()` or `.copy_src()`, storage buffers need
+    /// `.copy_src()` since they can't also be mapped.
+    /// This waits for the gpu to finish all submitted work, so avoid calling it every frame.
+    pub fn read_buffer<T: BufferContents>(&self, buffer: BufferHandle) -> Vec<T> {
+        self.buffers
+            .get(buffer)
+            .expect("Invalid buffer handle passed to read_buffer")
+            .read_data::<T>()
+    }
+
+    pub fn write_to_texture<T: TextureContents>(
+        &mut self,
+        texture: TextureHandle,
+        data: &[T::Data],
+    ) {
+        let texture = self
+            .textures
+            .get_mut(texture)
+            .expect("Invalid texture handle passed to write_to_texture");
+
+        texture.write_data::<T>(data, &self.config);
+    }
+
+    /// Writes `data` to one layer of a texture built with [`TextureBuilder::size_2d_array`]
+    /// or one face of a texture built with [`TextureBuilder::size_cube`]
+    pub fn write_to_texture_layer<T: TextureContents>(
+        &mut self,
+        texture: TextureHandle,
+        layer: u32,
+        data: &[T::Data],
+    ) {
+        let texture = self
+            .textures
+            .get_mut(texture)
+            .expect("Invalid texture handle passed to write_to_texture_layer");
+
+        texture.write_layer::<T>(layer, data, &self.config);
+    }
+
+    /// Reads a texture back to the cpu, for checking what a pass or compute shader wrote
+    ///
+    /// The texture has to be created with `.copy_src()`.
+    /// This waits for the gpu to finish all submitted work, so it's slow and meant for debugging.<br>
+    /// The pixels of each layer are tightly packed one row after another.
+    pub fn read_texture<T: TextureContents>(&self, texture: TextureHandle) -> Vec<T::Data> {
+        self.textures
+            .get(texture)
+            .expect("Invalid texture handle passed to read_texture")
+            .read_data::<T>()
+    }
+
+    /// Copies `source` into `target` with a draw, scaling it to fit and converting between formats
+    ///
+    /// Unlike a texture copy the textures can have different sizes and formats.
+    /// `source` has to be a filterable float texture created with `.texture()`
+    /// and `target` has to be created with `.render()`.
+    pub fn blit_texture(&mut self, source: TextureHandle, target: TextureHandle) {
+        let source = self
+            .textures
+            .get(source)
+            .expect("Invalid texture handle passed to blit_texture as the source");
+        let target = self
+            .textures
+            .get(target)
+            .expect("Invalid texture handle passed to blit_texture as the target");
+
+        source.validate_usages(TextureUsages::TEXTURE_BINDING, "be blitted from");
+        target.validate_usages(TextureUsages::RENDER_ATTACHMENT, "be blitted to");
+        assert!(
+            source.is_plain_2d() && target.is_plain_2d(),
+            "blit_texture only supports single layer, single sample 2d textures"
+        );
+        assert!(
+            matches!(
+                source.format().describe().sample_type,
+                TextureSampleType::Float { filterable: true }
+            ),
+            "Can't blit from a texture with format {:?}, it can't be filtered",
+            source.format()
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Blit Encoder"),
+            });
+
+        self.blit_cache
+            .get_or_insert_with(|| BlitCache::new(&self.device))
+            .blit(
+                &self.device,
+                &mut encoder,
+                &source.get_view(),
+                &target.get_view(),
+                target.format(),
+            );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        target.mark_initialized(true);
+    }
+
+    /// Panics if the color attachment `source` can't be resolved into `target`
+    pub(crate) fn validate_color_resolve(
+        &self,
+        pass_name: Option<&str>,
+        source: TextureHandle,
+        target: TextureHandle,
+    ) {
+        assert!(
+            source != FRAMEBUFFER,
+            "Render pass {pass_name:?} resolves the framebuffer which is never multisampled"
+        );
+        let source = self
+            .textures
+            .get(source)
+            .expect("Invalid TextureHandle added to a render pass");
+
+        let (target_format, target_extent) = if target == FRAMEBUFFER {
+            (self.config.format, None)
+        } else {
+            let target = self
+                .textures
+                .get(target)
+                .expect("Invalid TextureHandle passed to resolve_color_to");
+            target.validate_usages(TextureUsages::RENDER_ATTACHMENT, "be a resolve target");
+            assert!(
+                target.sample_count() == 1,
+                "Render pass {pass_name:?} resolves into a texture with {} samples, resolve \
+                 targets must have one",
+                target.sample_count()
+            );
+            (target.format(), Some(target.extent()))
+        };
+
+        assert!(
+            source.sample_count() > 1,
+            "Render pass {pass_name:?} resolves a color attachment that isn't multisampled"
+        );
+        assert!(
+            source.format() == target_format,
+            "Render pass {pass_name:?} resolves a {:?} attachment into a {target_format:?} \
+             texture, the formats must match",
+            source.format(),
+        );
+        assert!(
+            target_extent.is_none_or(|extent| extent == source.extent()),
+            "Render pass {pass_name:?} resolves a color attachment of size {:?} into a texture of \
+             size {target_extent:?}, the sizes must match",
+            source.extent(),
+        );
+    }
+
+    /// Validates a depth resolve and creates the pipeline for the target's format
+    pub(crate) fn prepare_depth_resolve(
+        &mut self,
+        pass_name: Option<&str>,
+        source: TextureHandle,
+        depth_op: Option<Operations<f32>>,
+        target: TextureHandle,
+    ) {
+        let source = self
+            .textures
+            .get(source)
+            .expect("Invalid TextureHandle added to a render pass as a depth attachment");
+        let target = self
+            .textures
+            .get(target)
+            .expect("Invalid TextureHandle passed to resolve_depth_to");
+
+        source.validate_usages(TextureUsages::TEXTURE_BINDING, "have its depth resolved");
+        target.validate_usages(
+            TextureUsages::RENDER_ATTACHMENT,
+            "be a depth resolve target",
+        );
+
+        assert!(
+            depth_op.is_some_and(|op| op.store),
+            "Render pass {pass_name:?} resolves depth so its depth attachment has to store depth"
+        );
+        assert!(
+            source.sample_count() > 1 && target.sample_count() == 1,
+            "Render pass {pass_name:?} resolves a depth attachment with {} samples into a texture \
+             with {}, resolves go from a multisampled texture to a single sampled one",
+            source.sample_count(),
+            target.sample_count(),
+        );
+        assert!(
+            source.format() == target.format(),
+            "Render pass {pass_name:?} resolves {:?} depth into a {:?} texture, the formats must \
+             match",
+            source.format(),
+            target.format(),
+        );
+        assert!(
+            source.format().describe().sample_type == TextureSampleType::Depth,
+            "Render pass {pass_name:?} resolves a {:?} attachment which has no depth",
+            source.format(),
+        );
+        assert!(
+            source.extent() == target.extent(),
+            "Render pass {pass_name:?} resolves depth of size {:?} into a texture of size {:?}, \
+             the sizes must match",
+            source.extent(),
+            target.extent(),
+        );
+
+        let format = target.format();
+        self.depth_resolve_cache
+            .get_or_insert_with(|| DepthResolveCache::new(&self.device))
+            .prepare(&self.device, format);
+    }
+
+    /// Moves the window of a dynamic buffer bound with [`BindGroupBuilder::bind_dynamic_uniform_buffer`]
+    ///
+    /// `offset` is in bytes and has to be a multiple of the device's
+    /// `min_uniform_buffer_offset_alignment`, which is 256 by default.
+    pub fn set_dynamic_offset(&mut self, bind_group: BindGroupHandle, binding: u32, offset: u32) {
+        let alignment = self.device.limits().min_uniform_buffer_offset_alignment;
+        assert!(
+            offset.is_multiple_of(alignment),
+            "Dynamic offset {offset} is not a multiple of the required alignment {alignment}"
+        );
+
+        self.bind_groups
+            .get_mut(bind_group)
+            .expect("Invalid BindGroupHandle passed to set_dynamic_offset")
+            .set_dynamic_offset(binding, offset);
+    }
+
+    /// Gives a render pipeline its own dynamic offsets for the bind group at `index`
+    ///
+    /// The pipeline uses `offsets` instead of the ones set with [`RenderManager::set_dynamic_offset`],
+    /// so pipelines sharing a bind group can each draw from a different part of a buffer.<br>
+    /// There has to be one offset for each dynamic binding in the group, in binding order,
+    /// and each has to be a multiple of the device's `min_uniform_buffer_offset_alignment`.
+    pub fn set_pipeline_dynamic_offsets(
+        &mut self,
+        pipeline: PipelineHandle,
+        index: usize,
+        offsets: &[u32],
+    ) {
+        let pipeline = self
+            .render_pipelines
+            .get_mut(pipeline)
+            .expect("Invalid PipelineHandle passed to set_pipeline_dynamic_offsets");
+        let bind_group = *pipeline
+            .bind_groups
+            .get(index)
+            .expect("Bind group index out of range in set_pipeline_dynamic_offsets");
+        let bind_group = self
+            .bind_groups
+            .get(bind_group)
+            .expect("Invalid BindGroupHandle found in a render pipeline");
+
+        assert!(
+            offsets.len() == bind_group.dynamic_count(),
+            "Bind group {:?} has {} dynamic bindings but pipeline {:?} was given {} offsets for it",
+            bind_group.name(),
+            bind_group.dynamic_count(),
+            pipeline.name,
+            offsets.len()
+        );
+
+        let alignment = self.device.limits().min_uniform_buffer_offset_alignment;
+        for offset in offsets {
+            assert!(
+                offset.is_multiple_of(alignment),
+                "Dynamic offset {offset} is not a multiple of the required alignment {alignment}"
+            );
+        }
+
+        pipeline.dynamic_offsets.retain(|(i, _)| *i != index);
+        pipeline.dynamic_offsets.push((index, offsets.to_vec()));
+    }
+
+    /// Gets a layout matching `entries`, only creating a new one if we haven't seen these entries yet
+    ///
+    /// Sharing layouts is what lets bind groups be swapped between pipelines,
+    /// pipelines are only compatible with bind groups that use the exact layout they were built with.
+    pub(crate) fn get_or_create_bind_group_layout(
+        &mut self,
+        label: Label<'_>,
+        entries: Vec<BindGroupLayoutEntry>,
+    ) -> Arc<BindGroupLayout> {
+        let device = &self.device;
+        self.bind_group_layouts
+            .entry(Self::bind_group_layout_key(entries))
+            .or_insert_with_key(|entries| {
+                Arc::new(
+                    device.create_bind_group_layout(&BindGroupLayoutDescriptor { label, entries }),
+                )
+            })
+            .clone()
+    }
+
+    /// Entry order doesn't affect compatibility so layouts are cached by their sorted entries
+    fn bind_group_layout_key(mut entries: Vec<BindGroupLayoutEntry>) -> Vec<BindGroupLayoutEntry> {
+        entries.sort_by_key(|e| e.binding);
+        entries
+    }
+
+    /// Replaces the bind group at `index` in a render pipeline
+    ///
+    /// The new bind group must be compatible with the one it replaces,
+    /// see [`BindGroup::is_compatible`] for what that means.
+    pub fn set_pipeline_bind_group(
+        &mut self,
+        pipeline: PipelineHandle,
+        index: usize,
+        bind_group: BindGroupHandle,
+    ) {
+        let pipeline = self
+            .render_pipelines
+            .get_mut(pipeline)
+            .expect("Invalid PipelineHandle passed to set_pipeline_bind_group");
+        let old_group = *pipeline
+            .bind_groups
+            .get(index)
+            .expect("Bind group index out of range in set_pipeline_bind_group");
+
+        let old = self
+            .bind_groups
+            .get(old_group)
+            .expect("Invalid BindGroupHandle found in a render pipeline");
+        let new = self
+            .bind_groups
+            .get(bind_group)
+            .expect("Invalid BindGroupHandle passed to set_pipeline_bind_group");
+
+        assert!(
+            new.is_compatible(old),
+            "Bind group {:?} has a different layout than bind group {:?} at index {index}",
+            new.name(),
+            old.name()
+        );
+
+        pipeline.bind_groups[index] = bind_group;
+    }
+
+    /// Sets the buffer used for vertex buffer `slot` of a render pipeline
+    ///
+    /// The buffer must match the layout the pipeline was built with.
+    /// Slots have to be filled in order, setting a slot past the last filled one will panic.
+    pub fn set_pipeline_vertex_buffer(
+        &mut self,
+        pipeline: PipelineHandle,
+        slot: usize,
+        buffer: BufferHandle,
+    ) {
+        self.set_pipeline_buffer(pipeline, slot, buffer, false)
+    }
+
+    /// Sets the buffer used for instance buffer `slot` of a render pipeline
+    ///
+    /// See [`RenderManager::set_pipeline_vertex_buffer`]
+    pub fn set_pipeline_instance_buffer(
+        &mut self,
+        pipeline: PipelineHandle,
+        slot: usize,
+        buffer: BufferHandle,
+    ) {
+        self.set_pipeline_buffer(pipeline, slot, buffer, true)
+    }
+
+    fn set_pipeline_buffer(
+        &mut self,
+        pipeline: PipelineHandle,
+        slot: usize,
+        buffer: BufferHandle,
+        instance: bool,
+    ) {
+        let pipeline = self
+            .render_pipelines
+            .get_mut(pipeline)
+            .expect("Invalid PipelineHandle passed to set_pipeline_vertex_buffer");
+        let raw_buffer = self
+            .buffers
+            .get(buffer)
+            .expect("Invalid BufferHandle passed to set_pipeline_vertex_buffer");
+
+        if instance {
+            pipeline.validate_vertex_buffer(slot + pipeline.instance_slot_start, raw_buffer);
+        } else {
+            assert!(
+                slot < pipeline.instance_slot_start,
+                "Vertex buffer slot {slot} is out of range for pipeline {:?}",
+                pipeline.name
+            );
+            pipeline.validate_vertex_buffer(slot, raw_buffer);
+        }
+
+        let buffers = if instance {
+            &mut pipeline.instance_buffers
+        } else {
+            &mut pipeline.vertex_buffers
+        };
+
+        match slot.cmp(&buffers.len()) {
+            std::cmp::Ordering::Less => buffers[slot] = buffer,
+            std::cmp::Ordering::Equal => buffers.push(buffer),
+            std::cmp::Ordering::Greater => panic!(
+                "Tried to set vertex buffer slot {slot} of pipeline {:?} before the slots before \
+                 it",
+                pipeline.name
+            ),
+        }
     }
 
     pub fn add_render_pass(&mut self, pass: RenderPass) -> RenderPassHandle {
@@ -237,57 +1076,441 @@ impl RenderManager {
         handle
     }
 
+    /// Compiles a wgsl shader, returning the existing handle if the same source was already registered
+    ///
+    /// Only the source is used to find cached shaders, so the label of a cache hit is ignored.
+    /// Use [`RenderManager::register_shader_uncached`] to always compile a new module.
     pub fn register_shader(&mut self, shader: &str, label: Label<'_>) -> ShaderHandle {
-        let module = self.device.create_shader_module(ShaderModuleDescriptor {
+        Self::cached_shader(&mut self.shader_cache, shader, || {
+            self.shaders
+                .add(Self::compile_shader(&self.device, shader, label))
+        })
+    }
+
+    /// Compiles a wgsl shader into a new module even if the same source was already registered
+    pub fn register_shader_uncached(&mut self, shader: &str, label: Label<'_>) -> ShaderHandle {
+        self.shaders
+            .add(Self::compile_shader(&self.device, shader, label))
+    }
+
+    fn compile_shader(device: &Device, shader: &str, label: Label<'_>) -> Shader {
+        let module = device.create_shader_module(ShaderModuleDescriptor {
             label,
             source: ShaderSource::Wgsl(shader.into()),
         });
 
-        self.shaders.add(Shader(module))
+        Shader::new(module, shader)
+    }
+
+    /// Returns the shader cached for `source`, only calling `compile` if there isn't one
+    fn cached_shader(
+        cache: &mut HashMap<String, ShaderHandle>,
+        source: &str,
+        compile: impl FnOnce() -> ShaderHandle,
+    ) -> ShaderHandle {
+        if let Some(handle) = cache.get(source) {
+            return *handle;
+        }
+
+        let handle = compile();
+        cache.insert(source.to_owned(), handle);
+        handle
+    }
+
+    pub fn register_shader_file(
+        &mut self,
+        shader: impl AsRef<Path>,
+        label: Label<'_>,
+    ) -> std::io::Result<ShaderHandle> {
+        let mut file = OpenOptions::new().read(true).open(shader)?;
+        let mut buf = String::with_capacity(file.metadata().map(|m| m.len() as usize).unwrap_or(0));
+        file.read_to_string(&mut buf)?;
+        Ok(self.register_shader(&buf, label))
+    }
+
+    pub(crate) fn get_shader(&self, handle: ShaderHandle) -> Option<&Shader> {
+        self.shaders.get(handle)
+    }
+
+    pub fn reorder_pipelines(
+        &mut self,
+        pass: RenderPassHandle,
+        pipelines: impl AsRef<[PipelineHandle]>,
+    ) {
+        if cfg!(debug_assertions) {
+            for pipeline in pipelines.as_ref() {
+                debug_assert!(
+                    self.render_pipelines.get(*pipeline).is_some(),
+                    "Invalid pipeline handle included in RenderManager::reorder_pipelines"
+                )
+            }
+        }
+
+        let pass_desc = self
+            .render_passes
+            .get(pass)
+            .expect("Invalid RenderPassHandle in reorder_pipelines");
+
+        let multiview = self.pipelines_multiview(pipelines.as_ref());
+
+        for pipeline in pipelines.as_ref() {
+            self.validate_pass_pipeline(
+                pass_desc.name.as_deref(),
+                &pass_desc.color_attachments,
+                pass_desc.depth_attachments.as_ref(),
+                multiview,
+                *pipeline,
+            );
+        }
+
+        let pass = self.render_passes.get_mut(pass).unwrap();
+        pass.reorder_pipelines(pipelines);
+        pass.multiview = multiview;
+    }
+
+    /// Adds a pipeline to the end of a render pass that's already been built
+    ///
+    /// The pipeline is validated against the pass's attachments like pipelines added with
+    /// [`RenderPassBuilder::add_pipeline`](crate::render_pass::RenderPassBuilder::add_pipeline).
+    pub fn add_pipeline_to_pass(&mut self, pass: RenderPassHandle, pipeline: PipelineHandle) {
+        let pass_desc = self
+            .render_passes
+            .get(pass)
+            .expect("Invalid RenderPassHandle in add_pipeline_to_pass");
+
+        let mut pipelines = pass_desc.pipelines.clone();
+        pipelines.push(pipeline);
+        let multiview = self.pipelines_multiview(&pipelines);
+
+        self.validate_pass_pipeline(
+            pass_desc.name.as_deref(),
+            &pass_desc.color_attachments,
+            pass_desc.depth_attachments.as_ref(),
+            multiview,
+            pipeline,
+        );
+
+        let pass = self.render_passes.get_mut(pass).unwrap();
+        pass.pipelines = pipelines;
+        pass.multiview = multiview;
+    }
+
+    /// Removes the first use of `pipeline` from a render pass without destroying the pipeline
+    ///
+    /// Returns false if the pipeline wasn't in the pass.
+    pub fn remove_pipeline_from_pass(
+        &mut self,
+        pass: RenderPassHandle,
+        pipeline: PipelineHandle,
+    ) -> bool {
+        let pass = self
+            .render_passes
+            .get_mut(pass)
+            .expect("Invalid RenderPassHandle in remove_pipeline_from_pass");
+
+        match pass.pipelines.iter().position(|p| *p == pipeline) {
+            Some(index) => {
+                pass.pipelines.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a pipeline to the end of a compute pass that's already been built
+    pub fn add_pipeline_to_compute_pass(
+        &mut self,
+        pass: ComputePassHandle,
+        pipeline: ComputePipelineHandle,
+    ) {
+        assert!(
+            self.compute_pipelines.get(pipeline).is_some(),
+            "Invalid ComputePipelineHandle in add_pipeline_to_compute_pass"
+        );
+
+        self.compute_passes
+            .get_mut(pass)
+            .expect("Invalid ComputePassHandle in add_pipeline_to_compute_pass")
+            .pipelines
+            .push(pipeline);
+    }
+
+    /// Removes the first use of `pipeline` from a compute pass without destroying the pipeline
+    ///
+    /// Returns false if the pipeline wasn't in the pass.
+    pub fn remove_pipeline_from_compute_pass(
+        &mut self,
+        pass: ComputePassHandle,
+        pipeline: ComputePipelineHandle,
+    ) -> bool {
+        let pass = self
+            .compute_passes
+            .get_mut(pass)
+            .expect("Invalid ComputePassHandle in remove_pipeline_from_compute_pass");
+
+        match pass.pipelines.iter().position(|p| *p == pipeline) {
+            Some(index) => {
+                pass.pipelines.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The label a bind group was built with
+    pub fn bind_group_name(&self, bind_group: BindGroupHandle) -> Option<&str> {
+        self.bind_groups
+            .get(bind_group)
+            .expect("Invalid BindGroupHandle passed to bind_group_name")
+            .name()
+    }
+
+    /// Every binding in a bind group and the kind of resource bound to it, sorted by binding
+    pub fn bind_group_bindings(&self, bind_group: BindGroupHandle) -> Vec<(u32, BindingKind)> {
+        self.bind_groups
+            .get(bind_group)
+            .expect("Invalid BindGroupHandle passed to bind_group_bindings")
+            .bindings()
+    }
+
+    /// Every bind group that binds `buffer`, these are the groups recreated when the buffer grows
+    pub fn bind_groups_using_buffer(&self, buffer: BufferHandle) -> Vec<BindGroupHandle> {
+        self.bind_groups
+            .handles_where(|group| group.depends_buffer(buffer))
+    }
+
+    /// Every bind group that binds `texture`, these are the groups recreated when the texture
+    /// is resized
+    pub fn bind_groups_using_texture(&self, texture: TextureHandle) -> Vec<BindGroupHandle> {
+        self.bind_groups
+            .handles_where(|group| group.depends_texture(texture))
+    }
+
+    /// Every bind group that binds `sampler`
+    pub fn bind_groups_using_sampler(&self, sampler: TextureSampleHandle) -> Vec<BindGroupHandle> {
+        self.bind_groups
+            .handles_where(|group| group.depends_sampler(sampler))
+    }
+
+    /// The label a render pipeline was built with
+    pub fn pipeline_name(&self, pipeline: PipelineHandle) -> Option<&str> {
+        self.render_pipelines
+            .get(pipeline)
+            .expect("Invalid PipelineHandle passed to pipeline_name")
+            .name
+            .as_deref()
+    }
+
+    /// The label a compute pipeline was built with
+    pub fn compute_pipeline_name(&self, pipeline: ComputePipelineHandle) -> Option<&str> {
+        self.compute_pipelines
+            .get(pipeline)
+            .expect("Invalid ComputePipelineHandle passed to compute_pipeline_name")
+            .name
+            .as_deref()
+    }
+
+    /// Whether a pipeline has no fragment shader and only writes depth
+    pub(crate) fn is_depth_only_pipeline(&self, pipeline: PipelineHandle) -> bool {
+        self.render_pipelines
+            .get(pipeline)
+            .is_some_and(|pipeline| pipeline.color_formats.is_empty())
+    }
+
+    /// Gets the multiview layer count used by a set of pipelines in a render pass
+    pub(crate) fn pipelines_multiview(&self, pipelines: &[PipelineHandle]) -> Option<NonZeroU32> {
+        pipelines
+            .first()
+            .and_then(|pipeline| self.render_pipelines.get(*pipeline))
+            .and_then(|pipeline| pipeline.multiview)
+    }
+
+    /// Checks that a pipeline writes to as many color targets as the pass has color attachments
+    /// and that the pass's attachments have enough layers for its multiview
+    pub(crate) fn validate_pass_pipeline(
+        &self,
+        pass_name: Option<&str>,
+        color_attachments: &[(TextureHandle, Operations<Color>)],
+        depth_attachment: Option<&DepthAttachment>,
+        multiview: Option<NonZeroU32>,
+        pipeline: PipelineHandle,
+    ) {
+        let pipeline = self
+            .render_pipelines
+            .get(pipeline)
+            .expect("Invalid PipelineHandle added to a render pass");
+
+        assert!(
+            pipeline.color_formats.len() == color_attachments.len(),
+            "Pipeline {:?} has {} color targets but render pass {pass_name:?} has {} color \
+             attachments, they must match",
+            pipeline.name,
+            pipeline.color_formats.len(),
+            color_attachments.len(),
+        );
+
+        for (i, ((texture, _), format)) in color_attachments
+            .iter()
+            .zip(&pipeline.color_formats)
+            .enumerate()
+        {
+            let attachment_format = if *texture == FRAMEBUFFER {
+                self.config.format
+            } else {
+                self.textures
+                    .get(*texture)
+                    .expect("Invalid TextureHandle added to a render pass")
+                    .format()
+            };
+
+            assert!(
+                attachment_format == *format,
+                "Pipeline {:?} writes {format:?} to color target {i} but render pass \
+                 {pass_name:?} has a {attachment_format:?} attachment there",
+                pipeline.name,
+            );
+        }
+
+        let attachments = color_attachments
+            .iter()
+            .map(|(texture, _)| *texture)
+            .chain(depth_attachment.map(|d| d.texture));
+        for texture in attachments {
+            let sample_count = if texture == FRAMEBUFFER {
+                1
+            } else {
+                self.textures
+                    .get(texture)
+                    .expect("Invalid TextureHandle added to a render pass")
+                    .sample_count()
+            };
+
+            assert!(
+                sample_count == pipeline.sample_count,
+                "Pipeline {:?} renders with {} samples but render pass {pass_name:?} has an \
+                 attachment with {sample_count}, they must match",
+                pipeline.name,
+                pipeline.sample_count,
+            );
+        }
+
+        assert!(
+            !pipeline.color_formats.is_empty() || depth_attachment.is_some(),
+            "Pipeline {:?} is depth only but render pass {pass_name:?} has no depth attachment",
+            pipeline.name,
+        );
+
+        assert!(
+            !(pipeline.depth_write && depth_attachment.is_some_and(|d| d.read_only)),
+            "Pipeline {:?} writes depth but render pass {pass_name:?} has a read only depth \
+             attachment",
+            pipeline.name,
+        );
+
+        assert!(
+            pipeline.multiview == multiview,
+            "Pipeline {:?} renders to {:?} multiview layers but render pass {pass_name:?} renders \
+             to {multiview:?}, all pipelines in a pass must match",
+            pipeline.name,
+            pipeline.multiview,
+        );
+
+        if let Some(layers) = multiview {
+            let textures = color_attachments
+                .iter()
+                .map(|(texture, _)| *texture)
+                .chain(depth_attachment.map(|d| d.texture));
+
+            for texture in textures {
+                assert!(
+                    texture != FRAMEBUFFER,
+                    "Render pass {pass_name:?} uses multiview so it can't render to the \
+                     framebuffer"
+                );
+
+                let array_layers = self
+                    .textures
+                    .get(texture)
+                    .expect("Invalid TextureHandle added to a render pass")
+                    .array_layers();
+
+                assert!(
+                    array_layers.is_some_and(|array_layers| array_layers >= layers.get()),
+                    "Render pass {pass_name:?} renders to {layers} multiview layers but has an \
+                     attachment with {array_layers:?} array layers"
+                );
+            }
+        }
+    }
+
+    /// Frees all gpu resources owned by the manager once the gpu is done with them
+    ///
+    /// Dropping the manager releases everything eventually, but in whatever order the
+    /// registries are dropped and without waiting on work that is still in flight.<br>
+    /// This waits for the device to be idle and destroys every buffer and texture before
+    /// dropping the rest, so the memory is freed by the time this returns.
+    /// The surface is always dropped before the window it was created from.
+    pub fn destroy(self) {
+        self.device.poll(Maintain::Wait);
+
+        for buffer in &self.buffers {
+            buffer.inner().destroy();
+        }
+
+        for texture in &self.textures {
+            texture.destroy();
+        }
+    }
+
+    /// Information about the adapter in use, including which backend was picked
+    pub fn adapter_info(&self) -> AdapterInfo {
+        self.adapter.get_info()
     }
 
-    pub fn register_shader_file(
-        &mut self,
-        shader: impl AsRef<Path>,
-        label: Label<'_>,
-    ) -> std::io::Result<ShaderHandle> {
-        let mut file = OpenOptions::new().read(true).open(shader)?;
-        let mut buf = String::with_capacity(file.metadata().map(|m| m.len() as usize).unwrap_or(0));
-        file.read_to_string(&mut buf)?;
-        Ok(self.register_shader(&buf, label))
+    /// The window the manager was created with
+    ///
+    /// Panics if the manager was created with [`RenderManager::new_with_surface`]
+    pub fn window(&self) -> &Window {
+        self.window
+            .as_ref()
+            .expect("RenderManager was created without a winit window")
     }
 
-    pub(crate) fn get_shader(&self, handle: ShaderHandle) -> Option<&Shader> {
-        self.shaders.get(handle)
+    pub fn surface_size(&self) -> PhysicalSize<u32> {
+        self.size
     }
 
-    pub fn reorder_pipelines(
-        &mut self,
-        pass: RenderPassHandle,
-        pipelines: impl AsRef<[PipelineHandle]>,
-    ) {
-        if cfg!(debug_assertions) {
-            for pipeline in pipelines.as_ref() {
-                debug_assert!(
-                    self.render_pipelines.get(*pipeline).is_some(),
-                    "Invalid pipeline handle included in RenderManager::reorder_pipelines"
-                )
-            }
-        }
+    /// Converts a pixel position on the surface, like a cursor position, to normalized device coordinates
+    pub fn pixel_to_ndc(&self, pixel: Vec2) -> Vec2 {
+        pixel.pixel_to_ndc(self.surface_size_vec())
+    }
 
-        let pass = self
-            .render_passes
-            .get_mut(pass)
-            .expect("Invalid RenderPassHandle in reorder_pipelines");
+    /// Converts normalized device coordinates to a pixel position on the surface
+    pub fn ndc_to_pixel(&self, ndc: Vec2) -> Vec2 {
+        ndc.ndc_to_pixel(self.surface_size_vec())
+    }
 
-        pass.reorder_pipelines(pipelines);
+    fn surface_size_vec(&self) -> Vec2 {
+        Vec2::new(self.size.width as f32, self.size.height as f32)
     }
 
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
-        self.size = size;
-        self.config.width = size.width;
-        self.config.height = size.height;
-        self.surface.configure(&self.device, &self.config);
+        self.resize_to(size.width, size.height)
+    }
+
+    /// Resizes the surface and any textures sized relative to it
+    ///
+    /// Resizing to a width or height of 0, which happens when a window is minimized, is ignored
+    /// since the surface can't be configured with that size.
+    pub fn resize_to(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.size = PhysicalSize::new(width, height);
+        self.config.width = width;
+        self.config.height = height;
+        self.configure_surface();
 
         let mut updated_textures = Vec::new();
 
@@ -304,14 +1527,69 @@ impl RenderManager {
                 group.recreate(&self.device, &self.buffers, &self.textures, &self.samplers);
             }
         }
+
+        for callback in &mut self.resize_callbacks {
+            callback(width, height);
+        }
+    }
+
+    /// Registers a callback that is called with the new width and height whenever the surface is resized
+    ///
+    /// Callbacks are called after the surface and any textures sized relative to it are recreated.
+    /// This is a good place to update things like camera aspect ratios.
+    /// Callbacks have to be `Send + Sync` so that the manager can still be shared between threads.
+    pub fn on_resize(&mut self, callback: Box<dyn FnMut(u32, u32) + Send + Sync>) {
+        self.resize_callbacks.push(callback);
     }
 
     pub fn recreate(&mut self) {
-        self.surface.configure(&self.device, &self.config);
+        self.configure_surface();
+    }
+
+    /// Sets how frames are presented, like `PresentMode::Immediate` to turn vsync off
+    ///
+    /// Falls back to `PresentMode::Fifo`, which is always supported, if the surface doesn't
+    /// support `mode`. Returns the present mode that ended up being used.
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> PresentMode {
+        let supported = self
+            .surface()
+            .get_capabilities(&self.adapter)
+            .present_modes
+            .contains(&mode);
+
+        self.config.present_mode = if supported { mode } else { PresentMode::Fifo };
+        self.configure_surface();
+        self.config.present_mode
+    }
+
+    /// How frames are presented to the surface, see [`RenderManager::set_present_mode`]
+    pub fn present_mode(&self) -> PresentMode {
+        self.config.present_mode
+    }
+
+    /// Lets the framebuffer be copied from, which is needed to capture frames from the window
+    ///
+    /// This is off by default since it can stop some platforms from using their fastest
+    /// presentation path.<br>
+    /// Not every platform supports copying from the surface. The web never does, and since wgpu
+    /// doesn't report the usages a surface supports, other platforms that don't will fail when
+    /// the surface is reconfigured.
+    pub fn set_framebuffer_copy_src(&mut self, enabled: bool) {
+        assert!(
+            !enabled || !cfg!(target_arch = "wasm32"),
+            "The surface can't be copied from on the web"
+        );
+
+        self.config.usage.set(TextureUsages::COPY_SRC, enabled);
+        self.configure_surface();
     }
 
-    pub fn render(&self) -> Result<(), SurfaceError> {
-        let surface_texture = self.surface.get_current_texture()?;
+    /// Runs every pass and presents the frame, returning what was rendered
+    ///
+    /// If the surface is outdated, like right after a resize, it's reconfigured and acquired
+    /// again once before giving up and returning [`SurfaceError::Outdated`].
+    pub fn render(&self) -> Result<FrameInfo, SurfaceError> {
+        let surface_texture = self.acquire_surface_texture()?;
         let surface_view = surface_texture
             .texture
             .create_view(&TextureViewDescriptor::default());
@@ -330,10 +1608,81 @@ impl RenderManager {
             }
         }
 
-        self.queue.submit(std::iter::once(command_encoder.finish()));
+        let submission_index = self.queue.submit(std::iter::once(command_encoder.finish()));
         surface_texture.present();
 
-        Ok(())
+        Ok(self.frame_info(submission_index))
+    }
+
+    fn surface(&self) -> &Surface {
+        self.surface
+            .as_ref()
+            .expect("Headless render managers have no surface")
+    }
+
+    fn configure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// Gets the next surface texture, reconfiguring the surface and trying again if it's outdated
+    fn acquire_surface_texture(&self) -> Result<SurfaceTexture, SurfaceError> {
+        // Every frame gets a new surface texture whose contents are undefined
+        self.framebuffer_initialized.store(false, Ordering::Relaxed);
+
+        match self.surface().get_current_texture() {
+            Err(SurfaceError::Outdated) => {
+                self.configure_surface();
+                self.surface().get_current_texture()
+            }
+            result => result,
+        }
+    }
+
+    /// [`RenderManager::render`] without the frame info
+    pub fn render_simple(&self) -> Result<(), SurfaceError> {
+        self.render().map(|_| ())
+    }
+
+    fn frame_info(&self, submission_index: SubmissionIndex) -> FrameInfo {
+        FrameInfo {
+            format: self.config.format,
+            size: self.size,
+            submission_index,
+        }
+    }
+
+    /// Blocks until the gpu has finished the frame
+    pub fn wait_for_frame(&self, frame: &FrameInfo) {
+        self.device.poll(Maintain::WaitForSubmissionIndex(
+            frame.submission_index.clone(),
+        ));
+    }
+
+    /// Starts recording a frame without running any passes
+    ///
+    /// Use this instead of [`RenderManager::render`] when you need to run passes in a custom order
+    /// or do extra work between them. Finish the frame with [`Frame::end_frame`].<br>
+    /// Outdated surfaces are retried once like in [`RenderManager::render`].
+    pub fn begin_frame(&mut self) -> Result<Frame<'_>, SurfaceError> {
+        let surface_texture = self.acquire_surface_texture()?;
+        let surface_view = surface_texture
+            .texture
+            .create_view(&TextureViewDescriptor::default());
+
+        let command_encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Main Render"),
+            });
+
+        Ok(Frame {
+            manager: self,
+            surface_texture,
+            surface_view,
+            command_encoder,
+        })
     }
 
     fn run_compute_pass(&self, pass: ComputePassHandle, command_encoder: &mut CommandEncoder) {
@@ -343,21 +1692,27 @@ impl RenderManager {
         });
 
         for pipeline in &pass_desc.pipelines {
-            let pipeline = self.compute_pipelines.get(*pipeline).unwrap();
+            let pipeline = self.compute_pipelines.get(*pipeline).unwrap_or_else(|| {
+                panic!(
+                    "Invalid ComputePipelineHandle in compute pass {:?}",
+                    pass_desc.name
+                )
+            });
 
             pass.set_pipeline(pipeline.inner());
 
             for (i, bind_group) in pipeline.bind_groups.iter().enumerate() {
-                pass.set_bind_group(
-                    i as u32,
-                    self.bind_groups
-                        .get(*bind_group)
-                        .expect("Invalid BindGroupHandle in a render pipeline")
-                        .inner(),
-                    &[],
-                );
+                let bind_group = self.bind_groups.get(*bind_group).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid BindGroupHandle in compute pipeline {:?}",
+                        pipeline.name
+                    )
+                });
+                pass.set_bind_group(i as u32, bind_group.inner(), &bind_group.dynamic_offsets());
             }
 
+            // wgpu adds a barrier before each dispatch for any resources the previous ones
+            // wrote, so there's no need for one between pipelines
             pass.dispatch_workgroups(
                 pipeline.work_groups[0],
                 pipeline.work_groups[1],
@@ -387,32 +1742,93 @@ impl RenderManager {
                     self.textures
                         .get(*texture)
                         .expect("Invalid TextureHandle found in a render pass")
-                        .get_view(),
+                        .get_pass_view(pass_desc.multiview),
                 ))
             };
         }
 
-        for ((_, op), view) in pass_desc.color_attachments.iter().zip(views.iter()) {
+        let resolve_views = pass_desc
+            .color_resolve_targets
+            .iter()
+            .map(|(index, target)| {
+                if *target == FRAMEBUFFER {
+                    self.framebuffer_initialized.store(true, Ordering::Relaxed);
+                    return (*index, None);
+                }
+
+                let target = self.textures.get(*target).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid TextureHandle used as a resolve target in render pass {:?}",
+                        pass_desc.name
+                    )
+                });
+                target.mark_initialized(true);
+                (*index, Some(target.get_view()))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, ((texture, op), view)) in pass_desc
+            .color_attachments
+            .iter()
+            .zip(views.iter())
+            .enumerate()
+        {
+            let mut op = *op;
+
+            if *texture == FRAMEBUFFER {
+                // The framebuffer attachment added to passes without color attachments loads,
+                // so the first pass drawing to a new surface texture has to clear it instead
+                if !self.framebuffer_initialized.load(Ordering::Relaxed) {
+                    if let LoadOp::Load = op.load {
+                        op.load = LoadOp::Clear(Color::TRANSPARENT);
+                    }
+                }
+                if op.store {
+                    self.framebuffer_initialized.store(true, Ordering::Relaxed);
+                }
+            } else {
+                let texture = self.textures.get(*texture).unwrap();
+                op.load = texture.initialized_load_op(op.load, Color::TRANSPARENT);
+                texture.mark_initialized(op.store);
+            }
+
+            let resolve_target = resolve_views
+                .iter()
+                .find(|(index, _)| *index == i)
+                .map(|(_, view)| view.as_ref().unwrap_or(surface_view));
+
             // TODO: add support for only enabling some attachements in a pass
             attachments.push(Some(RenderPassColorAttachment {
                 view: if let Some(v) = view { v } else { surface_view },
-                resolve_target: None,
-                ops: *op,
+                resolve_target,
+                ops: op,
             }));
         }
 
         let mut depth_stencil_view = None;
         let depth_stencil = if let Some(d) = &pass_desc.depth_attachments {
-            depth_stencil_view = Some(
-                self.textures
-                    .get(d.texture)
-                    .expect("Invalid TextureHandle in a render pass as a depth stencil attachment")
-                    .get_view(),
+            let texture = self
+                .textures
+                .get(d.texture)
+                .expect("Invalid TextureHandle in a render pass as a depth stencil attachment");
+            depth_stencil_view = Some(texture.get_pass_view(pass_desc.multiview));
+
+            let depth_ops = d.depth_op.map(|op| Operations {
+                load: texture.initialized_load_op(op.load, 0.0),
+                store: op.store,
+            });
+            let stencil_ops = d.stencil_op.map(|op| Operations {
+                load: texture.initialized_load_op(op.load, 0),
+                store: op.store,
+            });
+            texture.mark_initialized(
+                depth_ops.is_some_and(|op| op.store) || stencil_ops.is_some_and(|op| op.store),
             );
+
             Some(RenderPassDepthStencilAttachment {
                 view: depth_stencil_view.as_ref().unwrap(),
-                depth_ops: d.depth_op,
-                stencil_ops: d.stencil_op,
+                depth_ops,
+                stencil_ops,
             })
         } else {
             None
@@ -425,29 +1841,45 @@ impl RenderManager {
             depth_stencil_attachment: depth_stencil,
         });
 
+        let mut bound_groups = BoundGroups::default();
+
         for pipeline in &pass_desc.pipelines {
-            let pipeline = self
-                .render_pipelines
-                .get(*pipeline)
-                .expect("Invalid RenderPipelineHandle in a render pass");
+            let pipeline = self.render_pipelines.get(*pipeline).unwrap_or_else(|| {
+                panic!(
+                    "Invalid RenderPipelineHandle in render pass {:?}",
+                    pass_desc.name
+                )
+            });
             pass.set_pipeline(&pipeline.pipeline);
 
-            for (i, bind_group) in pipeline.bind_groups.iter().enumerate() {
-                pass.set_bind_group(
-                    i as u32,
-                    self.bind_groups
-                        .get(*bind_group)
-                        .expect("Invalid BindGroupHandle in a render pipeline")
-                        .inner(),
-                    &[],
-                );
+            bound_groups.start_pipeline(pipeline.bind_groups.len());
+
+            for (i, handle) in pipeline.bind_groups.iter().enumerate() {
+                let bind_group = self.bind_groups.get(*handle).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid BindGroupHandle in render pipeline {:?}",
+                        pipeline.name
+                    )
+                });
+                let offsets = pipeline
+                    .dynamic_offsets
+                    .iter()
+                    .find(|(index, _)| *index == i)
+                    .map(|(_, offsets)| offsets.clone())
+                    .unwrap_or_else(|| bind_group.dynamic_offsets());
+
+                if bound_groups.bind(i, *handle, &offsets) {
+                    pass.set_bind_group(i as u32, bind_group.inner(), &offsets);
+                }
             }
 
             if let Some(idx_buffer) = pipeline.index_buffers {
-                let idx_buffer = self
-                    .buffers
-                    .get(idx_buffer)
-                    .expect("Invalid BufferHandle used as an index buffer in a render pipeline");
+                let idx_buffer = self.buffers.get(idx_buffer).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid BufferHandle used as an index buffer in render pipeline {:?}",
+                        pipeline.name
+                    )
+                });
                 let size = idx_buffer.len();
                 pass.set_index_buffer(
                     idx_buffer.inner().slice(..),
@@ -459,9 +1891,12 @@ impl RenderManager {
                 let mut vertex_buffer_size = None;
 
                 for (i, vertex_buffer) in pipeline.vertex_buffers.iter().enumerate() {
-                    let buffer = self.buffers.get(*vertex_buffer).expect(
-                        "Invalid BufferHandle used as a vertex buffer in a render pipeline",
-                    );
+                    let buffer = self.buffers.get(*vertex_buffer).unwrap_or_else(|| {
+                        panic!(
+                            "Invalid BufferHandle used as a vertex buffer in render pipeline {:?}",
+                            pipeline.name
+                        )
+                    });
 
                     if let Some(size) = vertex_buffer_size {
                         debug_assert!(
@@ -474,40 +1909,38 @@ impl RenderManager {
                         vertex_buffer_size = Some(buffer.len());
                     }
 
+                    if cfg!(debug_assertions) {
+                        pipeline.validate_vertex_buffer(i, buffer);
+                    }
+
                     pass.set_vertex_buffer(i as u32, buffer.inner().slice(..))
                 }
 
-                let max_vertex_buffer = pipeline.vertex_buffers.len();
-                let mut instance_size = None;
-
-                for (i, instance_buffer) in pipeline.instance_buffers.iter().enumerate() {
-                    let buffer = self.buffers.get(*instance_buffer).expect(
-                        "Invalid BufferHandle used as an instance buffer in a render pipeline",
-                    );
+                let instance_count = self.set_instance_buffers(&mut pass, pipeline);
 
-                    if let Some(size) = instance_size {
-                        debug_assert!(
-                            buffer.len() as u32 == size,
-                            "Instance buffers in render pipeline have different lengths, ensure \
-                             all instance buffers have the same length",
-                        )
-                    } else {
-                        instance_size = Some(buffer.len() as u32);
-                    }
+                let (index_range, base_vertex) =
+                    pipeline.index_draw.clone().unwrap_or((0 .. size as u32, 0));
+                debug_assert!(
+                    index_range.end as u64 <= size,
+                    "index_draw range {index_range:?} is outside of the index buffer of length \
+                     {size}"
+                );
 
-                    // We ensure that instance buffers come after vertex buffers
-                    pass.set_vertex_buffer((i + max_vertex_buffer) as u32, buffer.inner().slice(..))
+                if let Some(indirect) = pipeline.multi_indirect {
+                    self.draw_multi_indirect(&mut pass, indirect, true);
+                } else {
+                    pass.draw_indexed(index_range, base_vertex, 0 .. instance_count);
                 }
-
-                pass.draw_indexed(0 .. size as u32, 0, 0 .. instance_size.unwrap_or(1));
             } else {
                 let mut vertex_buffer_size = None;
 
                 for (i, vertex_buffer) in pipeline.vertex_buffers.iter().enumerate() {
-                    let buffer = self
-                        .buffers
-                        .get(*vertex_buffer)
-                        .expect("Invalid BufferHandle in a render pipeline");
+                    let buffer = self.buffers.get(*vertex_buffer).unwrap_or_else(|| {
+                        panic!(
+                            "Invalid BufferHandle used as a vertex buffer in render pipeline {:?}",
+                            pipeline.name
+                        )
+                    });
 
                     if let Some(size) = vertex_buffer_size {
                         debug_assert!(
@@ -520,12 +1953,144 @@ impl RenderManager {
                         vertex_buffer_size = Some(buffer.len());
                     }
 
+                    if cfg!(debug_assertions) {
+                        pipeline.validate_vertex_buffer(i, buffer);
+                    }
+
                     pass.set_vertex_buffer(i as u32, buffer.inner().slice(..))
                 }
 
-                // If no vertex buffers were attached we just default to drawing one vertex
-                // TODO: add a way to specify vertex count when no vertex buffers were attached
-                pass.draw(0 .. vertex_buffer_size.unwrap_or(1) as u32, 0 .. 1);
+                let instance_count = self.set_instance_buffers(&mut pass, pipeline);
+
+                if let (Some(count), Some(size)) = (pipeline.vertex_count, vertex_buffer_size) {
+                    debug_assert!(
+                        count as u64 <= size,
+                        "Pipeline {:?} draws {count} vertices but its vertex buffers only have \
+                         {size}",
+                        pipeline.name
+                    );
+                }
+                // Without vertex buffers or a vertex count we just default to drawing one vertex
+                let vertex_count = pipeline
+                    .vertex_count
+                    .or(vertex_buffer_size.map(|size| size as u32))
+                    .unwrap_or(1);
+
+                if let Some(indirect) = pipeline.multi_indirect {
+                    self.draw_multi_indirect(&mut pass, indirect, false);
+                } else {
+                    pass.draw(0 .. vertex_count, 0 .. instance_count);
+                }
+            }
+        }
+
+        // The resolve is its own pass so this one has to end first
+        drop(pass);
+
+        if let Some(depth) = &pass_desc.depth_attachments {
+            if let Some(target) = depth.resolve_target {
+                let source = self.textures.get(depth.texture).unwrap();
+                let target = self.textures.get(target).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid TextureHandle used as a depth resolve target in render pass {:?}",
+                        pass_desc.name
+                    )
+                });
+
+                self.depth_resolve_cache.as_ref().unwrap().resolve(
+                    &self.device,
+                    command_encoder,
+                    &source.get_depth_view(),
+                    &target.get_view(),
+                    target.format(),
+                );
+                target.mark_initialized(true);
+            }
+        }
+    }
+
+    /// Binds the instance buffers of `pipeline` after its vertex buffers and returns the number
+    /// of instances to draw
+    ///
+    /// This is the pipeline's explicit instance count if it has one, otherwise the length of
+    /// its instance buffers or 1 if it has none.
+    fn set_instance_buffers<'a>(
+        &'a self,
+        pass: &mut RawRenderPass<'a>,
+        pipeline: &'a RenderPipeline,
+    ) -> u32 {
+        let mut instance_size = None;
+
+        for (i, instance_buffer) in pipeline.instance_buffers.iter().enumerate() {
+            let buffer = self.buffers.get(*instance_buffer).unwrap_or_else(|| {
+                panic!(
+                    "Invalid BufferHandle used as an instance buffer in render pipeline {:?}",
+                    pipeline.name
+                )
+            });
+
+            if let Some(size) = instance_size {
+                debug_assert!(
+                    buffer.len() as u32 == size,
+                    "Instance buffers in render pipeline have different lengths, ensure all \
+                     instance buffers have the same length",
+                )
+            } else {
+                instance_size = Some(buffer.len() as u32);
+            }
+
+            // We ensure that instance buffers come after vertex buffers
+            let slot = i + pipeline.instance_slot_start;
+
+            if cfg!(debug_assertions) {
+                pipeline.validate_vertex_buffer(slot, buffer);
+            }
+
+            pass.set_vertex_buffer(slot as u32, buffer.inner().slice(..))
+        }
+
+        if let (Some(count), Some(size)) = (pipeline.instance_count, instance_size) {
+            debug_assert!(
+                count <= size,
+                "Pipeline {:?} draws {count} instances but its instance buffers only have {size}",
+                pipeline.name
+            );
+        }
+
+        pipeline.instance_count.or(instance_size).unwrap_or(1)
+    }
+
+    /// Issues `count` indirect draws, falling back to one draw call per command
+    /// when the device doesn't support `MULTI_DRAW_INDIRECT`
+    fn draw_multi_indirect<'a>(
+        &'a self,
+        pass: &mut RawRenderPass<'a>,
+        (buffer, offset, count): (BufferHandle, u64, u32),
+        indexed: bool,
+    ) {
+        let buffer = self
+            .buffers
+            .get(buffer)
+            .expect("Invalid BufferHandle used as an indirect buffer in a render pipeline")
+            .inner();
+
+        if self
+            .device
+            .features()
+            .contains(Features::MULTI_DRAW_INDIRECT)
+        {
+            if indexed {
+                pass.multi_draw_indexed_indirect(buffer, offset, count);
+            } else {
+                pass.multi_draw_indirect(buffer, offset, count);
+            }
+        } else if indexed {
+            for i in 0 .. count as u64 {
+                pass.draw_indexed_indirect(buffer, offset + i * DRAW_INDEXED_INDIRECT_SIZE);
+            }
+        } else {
+            for i in 0 .. count as u64 {
+                pass.draw_indirect(buffer, offset + i * DRAW_INDIRECT_SIZE);
             }
         }
     }
@@ -605,3 +2170,469 @@ pub enum PassHandle {
     RenderPass(RenderPassHandle),
     ComputePass(ComputePassHandle),
 }
+
+impl From<RenderPassHandle> for PassHandle {
+    fn from(handle: RenderPassHandle) -> Self {
+        PassHandle::RenderPass(handle)
+    }
+}
+
+impl From<ComputePassHandle> for PassHandle {
+    fn from(handle: ComputePassHandle) -> Self {
+        PassHandle::ComputePass(handle)
+    }
+}
+
+/// What was rendered in a frame, returned by [`RenderManager::render`] and [`Frame::end_frame`]
+#[derive(Clone, Debug)]
+pub struct FrameInfo {
+    /// The format of the surface texture
+    pub format: TextureFormat,
+    /// The size of the surface texture, which may lag behind the window while it's being resized
+    pub size: PhysicalSize<u32>,
+    /// Can be passed to [`RenderManager::wait_for_frame`] to wait for the gpu to finish the frame
+    pub submission_index: SubmissionIndex,
+}
+
+/// A frame that is currently being recorded, created by [`RenderManager::begin_frame`]
+///
+/// Nothing is sent to the gpu until [`Frame::end_frame`] is called.
+pub struct Frame<'a> {
+    manager: &'a mut RenderManager,
+    surface_texture: SurfaceTexture,
+    surface_view: TextureView,
+    command_encoder: CommandEncoder,
+}
+
+impl<'a> Frame<'a> {
+    /// Records a pass into the frame
+    ///
+    /// Passes don't need to have been added to the pass order and can be run more than once.
+    pub fn run_pass(&mut self, pass: impl Into<PassHandle>) {
+        match pass.into() {
+            PassHandle::RenderPass(pass) =>
+                self.manager
+                    .run_render_pass(pass, &mut self.command_encoder, &self.surface_view),
+            PassHandle::ComputePass(pass) => self
+                .manager
+                .run_compute_pass(pass, &mut self.command_encoder),
+        }
+    }
+
+    /// Records every pass in the order they were added, the same as [`RenderManager::render`]
+    pub fn run_all_passes(&mut self) {
+        for pass in &self.manager.passes {
+            match pass {
+                PassHandle::RenderPass(pass) => self.manager.run_render_pass(
+                    pass,
+                    &mut self.command_encoder,
+                    &self.surface_view,
+                ),
+                PassHandle::ComputePass(pass) => self
+                    .manager
+                    .run_compute_pass(pass, &mut self.command_encoder),
+            }
+        }
+    }
+
+    /// Writes data to a buffer, see [`RenderManager::write_to_buffer`]
+    ///
+    /// Buffer writes are applied when the frame is submitted, before any of its passes run.<br>
+    /// So writing to a buffer between two passes does not give the passes different data.
+    pub fn write_to_buffer<T: BufferContents>(&mut self, buffer: BufferHandle, data: &[T]) {
+        self.manager.write_to_buffer(buffer, data)
+    }
+
+    pub fn manager(&mut self) -> &mut RenderManager {
+        self.manager
+    }
+
+    /// Submits everything recorded in the frame and presents it to the surface
+    pub fn end_frame(self) -> FrameInfo {
+        let submission_index = self
+            .manager
+            .queue
+            .submit(std::iter::once(self.command_encoder.finish()));
+        self.surface_texture.present();
+
+        self.manager.frame_info(submission_index)
+    }
+}
+
+/// The bind group and dynamic offsets set at each slot of a render pass,
+/// used to skip setting groups shared between pipelines again
+#[derive(Default)]
+struct BoundGroups {
+    slots: Vec<(BindGroupHandle, Vec<u32>)>,
+}
+
+impl BoundGroups {
+    /// Slots past the ones the next pipeline uses are stale for the pipelines after it
+    fn start_pipeline(&mut self, bind_group_count: usize) {
+        self.slots.truncate(bind_group_count);
+    }
+
+    /// Records `bind_group` at `slot`, returns false if it was already set there with the same offsets
+    fn bind(&mut self, slot: usize, bind_group: BindGroupHandle, offsets: &[u32]) -> bool {
+        if let Some(bound) = self.slots.get_mut(slot) {
+            if bound.0 == bind_group && bound.1 == offsets {
+                return false;
+            }
+
+            *bound = (bind_group, offsets.to_vec());
+        } else {
+            self.slots.push((bind_group, offsets.to_vec()));
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use wgpu::{
+        AdapterInfo,
+        Backend,
+        Backends,
+        BindGroupLayoutEntry,
+        BindingType,
+        BufferBindingType,
+        CommandEncoderDescriptor,
+        CompareFunction,
+        DepthBiasState,
+        DeviceType,
+        Features,
+        FrontFace,
+        PrimitiveTopology,
+        SamplerBindingType,
+        ShaderStages,
+        StencilState,
+        TextureSampleType,
+        TextureUsages,
+        TextureViewDimension,
+    };
+
+    use super::{BoundGroups, RenderManager, RenderManagerError};
+    use crate::{
+        handle::Handle,
+        texture::{Depth, Norm, Srgb},
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn render_manager_is_send_sync() {
+        assert_send_sync::<RenderManager>();
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(
+            RenderManagerError::NoAdapter(Backends::VULKAN).to_string(),
+            "No graphics adapter that can draw to the window was found for backends VULKAN"
+        );
+        assert!(RenderManagerError::MissingFeatures(
+            AdapterInfo {
+                name: "Test".to_owned(),
+                vendor: 0,
+                device: 0,
+                device_type: DeviceType::Cpu,
+                driver: String::new(),
+                driver_info: String::new(),
+                backend: Backend::Vulkan,
+            },
+            Features::MULTIVIEW,
+        )
+        .to_string()
+        .ends_with("doesn't support the requested features MULTIVIEW"));
+    }
+
+    #[test]
+    fn bind_group_layout_key_ignores_entry_order() {
+        let uniform = BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let sampler = BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+        };
+
+        assert_eq!(
+            RenderManager::bind_group_layout_key(vec![uniform, sampler]),
+            RenderManager::bind_group_layout_key(vec![sampler, uniform])
+        );
+        assert_ne!(
+            RenderManager::bind_group_layout_key(vec![uniform, sampler]),
+            RenderManager::bind_group_layout_key(vec![uniform, BindGroupLayoutEntry {
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ..sampler
+            }])
+        );
+    }
+
+    #[test]
+    fn shared_bind_groups_are_set_once() {
+        let camera = Handle::new(0);
+        let material = Handle::new(1);
+        let mut bound = BoundGroups::default();
+
+        bound.start_pipeline(2);
+        assert!(bound.bind(0, camera, &[]));
+        assert!(bound.bind(1, material, &[]));
+
+        bound.start_pipeline(2);
+        assert!(!bound.bind(0, camera, &[]));
+        assert!(!bound.bind(1, material, &[]));
+
+        // A different dynamic offset has to be set again
+        bound.start_pipeline(1);
+        assert!(bound.bind(0, camera, &[256]));
+    }
+
+    #[test]
+    fn unused_slots_are_stale() {
+        let camera = Handle::new(0);
+        let material = Handle::new(1);
+        let mut bound = BoundGroups::default();
+
+        bound.start_pipeline(2);
+        assert!(bound.bind(0, camera, &[]));
+        assert!(bound.bind(1, material, &[]));
+
+        bound.start_pipeline(1);
+        assert!(!bound.bind(0, camera, &[]));
+
+        bound.start_pipeline(2);
+        assert!(!bound.bind(0, camera, &[]));
+        assert!(bound.bind(1, material, &[]));
+    }
+
+    #[test]
+    fn same_source_gets_same_shader() {
+        let mut cache = HashMap::new();
+        let mut compiled = 0;
+        let mut compile = || {
+            compiled += 1;
+            Handle::new(compiled)
+        };
+
+        let a = RenderManager::cached_shader(&mut cache, "a", &mut compile);
+        let b = RenderManager::cached_shader(&mut cache, "b", &mut compile);
+        assert!(a != b);
+        assert!(RenderManager::cached_shader(&mut cache, "a", &mut compile) == a);
+        assert!(RenderManager::cached_shader(&mut cache, "b", &mut compile) == b);
+        assert_eq!(compiled, 2);
+    }
+
+    #[test]
+    fn destroy_in_a_loop() {
+        for _ in 0 .. 3 {
+            let Some(mut manager) = RenderManager::new_headless() else {
+                return;
+            };
+
+            manager
+                .buffer_builder::<u32>(Some("Destroyed Buffer"))
+                .storage()
+                .build_init([1, 2, 3, 4]);
+            manager
+                .texture_builder::<Srgb<Norm<[u8; 4]>>>(Some("Destroyed Texture"))
+                .size_2d(16, 16)
+                .usage(TextureUsages::TEXTURE_BINDING)
+                .build();
+
+            manager.destroy();
+        }
+    }
+
+    #[test]
+    fn dispatches_see_earlier_writes_in_the_pass() {
+        let Some(mut manager) = RenderManager::new_headless() else {
+            return;
+        };
+
+        let shader = manager.register_shader(
+            "
+            @group(0) @binding(0) var<storage, read_write> data: array<vec2<u32>>;
+
+            @compute @workgroup_size(64)
+            fn fill(@builtin(global_invocation_id) id: vec3<u32>) {
+                data[id.x] = vec2<u32>(id.x, id.x + 1u);
+            }
+
+            @compute @workgroup_size(64)
+            fn double(@builtin(global_invocation_id) id: vec3<u32>) {
+                data[id.x] = data[id.x] * 2u;
+            }
+            ",
+            None,
+        );
+        let buffer = manager
+            .buffer_builder::<[u32; 2]>(None)
+            .storage()
+            .copy_src()
+            .build_init(vec![[0; 2]; 256]);
+        let bind_group = manager
+            .bind_group_builder(None)
+            .bind_storage_buffer::<[u32; 2]>(0, ShaderStages::COMPUTE, false, None, buffer)
+            .build();
+
+        let fill = manager
+            .compute_pipeline_builder(None)
+            .set_shader(shader, "fill")
+            .add_bind_group(bind_group)
+            .work_groups([4, 1, 1])
+            .build();
+        let double = manager
+            .compute_pipeline_builder(None)
+            .set_shader(shader, "double")
+            .add_bind_group(bind_group)
+            .work_groups([4, 1, 1])
+            .build();
+        let pass = manager
+            .compute_pass_builder(None)
+            .add_pipeline(fill)
+            .add_pipeline(double)
+            .build();
+
+        let mut encoder = manager
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        manager.run_compute_pass(pass, &mut encoder);
+        manager.queue.submit(std::iter::once(encoder.finish()));
+
+        let expected = (0 .. 256).map(|i| [i * 2, i * 2 + 2]).collect::<Vec<_>>();
+        assert_eq!(manager.read_buffer::<[u32; 2]>(buffer), expected);
+    }
+
+    #[test]
+    fn textures_created_with_data_can_be_sampled() {
+        let Some(mut manager) = RenderManager::new_headless() else {
+            return;
+        };
+
+        // 8 byte rows, far from the 256 bytes copies have to be aligned to
+        let pixels = (0 .. 4u8)
+            .map(|i| [i * 60, 255 - i * 60, i, 255])
+            .collect::<Vec<_>>();
+        let texture = manager.create_texture_with_data::<Norm<[u8; 4]>>(
+            None,
+            2,
+            2,
+            TextureUsages::empty(),
+            &pixels,
+        );
+
+        let shader = manager.register_shader(
+            "
+            @group(0) @binding(0) var texture: texture_2d<f32>;
+            @group(0) @binding(1) var<storage, read_write> texels: array<vec4<u32>>;
+
+            @compute @workgroup_size(2, 2)
+            fn sample(@builtin(global_invocation_id) id: vec3<u32>) {
+                let texel = textureLoad(texture, vec2<i32>(id.xy), 0);
+                texels[id.y * 2u + id.x] = vec4<u32>(round(texel * 255.0));
+            }
+            ",
+            None,
+        );
+        let buffer = manager
+            .buffer_builder::<[u32; 4]>(None)
+            .storage()
+            .copy_src()
+            .build_init(vec![[0; 4]; 4]);
+        let bind_group = manager
+            .bind_group_builder(None)
+            .bind_texture(
+                0,
+                ShaderStages::COMPUTE,
+                TextureSampleType::Float { filterable: true },
+                TextureViewDimension::D2,
+                false,
+                texture,
+            )
+            .bind_storage_buffer::<[u32; 4]>(1, ShaderStages::COMPUTE, false, None, buffer)
+            .build();
+        let pipeline = manager
+            .compute_pipeline_builder(None)
+            .set_shader(shader, "sample")
+            .add_bind_group(bind_group)
+            .work_groups([1, 1, 1])
+            .build();
+        let pass = manager
+            .compute_pass_builder(None)
+            .add_pipeline(pipeline)
+            .build();
+
+        let mut encoder = manager
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        manager.run_compute_pass(pass, &mut encoder);
+        manager.queue.submit(std::iter::once(encoder.finish()));
+
+        let expected = pixels
+            .iter()
+            .map(|pixel| pixel.map(u32::from))
+            .collect::<Vec<_>>();
+        assert_eq!(manager.read_buffer::<[u32; 4]>(buffer), expected);
+    }
+
+    #[test]
+    fn depth_only_pipelines_skip_the_framebuffer() {
+        let Some(mut manager) = RenderManager::new_headless() else {
+            return;
+        };
+
+        let shader = manager.register_shader(
+            "
+            @vertex
+            fn shadow(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(index % 2u), f32(index / 2u), 0.5, 1.0);
+            }
+            ",
+            None,
+        );
+        let depth = manager
+            .texture_builder::<Depth<f32>>(None)
+            .size_2d(64, 64)
+            .render()
+            .build();
+        let pipeline = manager
+            .render_pipeline_builder(None)
+            .vertex_shader(shader, "shadow")
+            .topology(PrimitiveTopology::TriangleList)
+            .front_face(FrontFace::Ccw)
+            .depth_stencil::<Depth<f32>>(
+                true,
+                CompareFunction::Less,
+                StencilState::default(),
+                DepthBiasState::default(),
+            )
+            .vertex_count(3)
+            .build();
+        let pass = manager
+            .render_pass_builder(None)
+            .add_depth_stencil_attachment(depth, Some((Some(1.0), true)), None)
+            .add_pipeline(pipeline)
+            .build();
+
+        assert!(manager.is_depth_only_pipeline(pipeline));
+        assert!(manager
+            .render_passes
+            .get(pass)
+            .unwrap()
+            .color_attachments
+            .is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{Error, ErrorKind, Read},
+    path::Path,
+    str::SplitWhitespace,
+};
+
+use petra_math::{Vec2, Vec3};
+use wgpu::Label;
+
+use crate::{buffer::BufferHandle, manager::RenderManager, vertex::Vertex};
+
+/// A vertex parsed from an OBJ file
+///
+/// Attributes the file doesn't have for a vertex are left as 0.<br>
+/// Implement `From<ObjVertex>` for your own vertex type to load it with [`RenderManager::load_obj`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ObjVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+}
+
+/// The triangles of an OBJ file with vertices shared between faces welded together
+#[derive(Clone, Debug, Default)]
+pub struct ObjMesh {
+    pub vertices: Vec<ObjVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl ObjMesh {
+    /// Parses the positions, normals, uvs, and faces of an OBJ file, ignoring everything else
+    ///
+    /// Faces with more than 3 vertices are split into a fan of triangles.
+    pub fn parse(source: &str) -> std::io::Result<ObjMesh> {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+
+        let mut mesh = ObjMesh::default();
+        // Maps the position, uv, and normal indices of a face vertex to the welded vertex
+        let mut welded = HashMap::new();
+
+        for (line_number, line) in source.lines().enumerate() {
+            let line_number = line_number + 1;
+            let mut parts = line.split_whitespace();
+
+            match parts.next() {
+                Some("v") => positions.push(Vec3::new(
+                    parse_float(&mut parts, line_number)?,
+                    parse_float(&mut parts, line_number)?,
+                    parse_float(&mut parts, line_number)?,
+                )),
+                Some("vn") => normals.push(Vec3::new(
+                    parse_float(&mut parts, line_number)?,
+                    parse_float(&mut parts, line_number)?,
+                    parse_float(&mut parts, line_number)?,
+                )),
+                Some("vt") => uvs.push(Vec2::new(
+                    parse_float(&mut parts, line_number)?,
+                    // OBJ uvs start at the bottom left, wgpu's start at the top left
+                    1.0 - parse_float(&mut parts, line_number)?,
+                )),
+                Some("f") => {
+                    let mut face = Vec::new();
+
+                    for vertex in parts {
+                        let mut indices = vertex.split('/');
+                        let key = (
+                            parse_index(indices.next(), positions.len(), line_number)?
+                                .ok_or_else(|| invalid_data(line_number, "missing position"))?,
+                            parse_index(indices.next(), uvs.len(), line_number)?,
+                            parse_index(indices.next(), normals.len(), line_number)?,
+                        );
+
+                        let index = *welded.entry(key).or_insert_with(|| {
+                            let (position, uv, normal) = key;
+                            mesh.vertices.push(ObjVertex {
+                                position: positions[position],
+                                normal: normal.map(|i| normals[i]).unwrap_or_default(),
+                                uv: uv.map(|i| uvs[i]).unwrap_or_default(),
+                            });
+                            mesh.vertices.len() as u32 - 1
+                        });
+                        face.push(index);
+                    }
+
+                    if face.len() < 3 {
+                        return Err(invalid_data(line_number, "faces need at least 3 vertices"));
+                    }
+
+                    for i in 1 .. face.len() - 1 {
+                        mesh.indices
+                            .extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(mesh)
+    }
+}
+
+fn invalid_data(line_number: usize, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Invalid OBJ on line {line_number}: {message}"),
+    )
+}
+
+fn parse_float(parts: &mut SplitWhitespace, line_number: usize) -> std::io::Result<f32> {
+    parts
+        .next()
+        .and_then(|part| part.parse().ok())
+        .ok_or_else(|| invalid_data(line_number, "expected a number"))
+}
+
+/// Parses a 1 based or negative relative index into an index into a list of `len` elements
+fn parse_index(
+    part: Option<&str>,
+    len: usize,
+    line_number: usize,
+) -> std::io::Result<Option<usize>> {
+    let part = match part {
+        Some(part) if !part.is_empty() => part,
+        _ => return Ok(None),
+    };
+
+    let index = part
+        .parse::<isize>()
+        .map_err(|_| invalid_data(line_number, "expected an index"))?;
+    let index = if index < 0 {
+        len as isize + index
+    } else {
+        index - 1
+    };
+
+    if index < 0 || index as usize >= len {
+        return Err(invalid_data(line_number, "index out of range"));
+    }
+
+    Ok(Some(index as usize))
+}
+
+impl RenderManager {
+    /// Loads an OBJ file into a vertex buffer of `V`s and a u32 index buffer
+    ///
+    /// Vertices used by more than one face are only stored once.
+    pub fn load_obj<V: Vertex + From<ObjVertex>>(
+        &mut self,
+        path: impl AsRef<Path>,
+        label: Label<'_>,
+    ) -> std::io::Result<(BufferHandle, BufferHandle)> {
+        let mut file = OpenOptions::new().read(true).open(path)?;
+        let mut buf = String::with_capacity(file.metadata().map(|m| m.len() as usize).unwrap_or(0));
+        file.read_to_string(&mut buf)?;
+
+        let mesh = ObjMesh::parse(&buf)?;
+
+        let vertices = self
+            .buffer_builder::<V>(label)
+            .vertex()
+            .build_init(mesh.vertices.into_iter().map(V::from).collect::<Vec<_>>());
+        let indices = self
+            .buffer_builder::<u32>(label)
+            .index()
+            .build_init(mesh.indices);
+
+        Ok((vertices, indices))
+    }
+}
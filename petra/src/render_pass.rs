@@ -1,3 +1,5 @@
+use std::num::NonZeroU32;
+
 use wgpu::{Color, Label, LoadOp, Operations};
 
 use crate::{
@@ -12,8 +14,12 @@ pub type RenderPassHandle = Handle<RenderPass>;
 pub struct RenderPass {
     pub name: Option<String>,
     pub color_attachments: Vec<(TextureHandle, Operations<Color>)>,
+    /// The index of a multisampled color attachment and the texture it's resolved into
+    pub color_resolve_targets: Vec<(usize, TextureHandle)>,
     pub depth_attachments: Option<DepthAttachment>,
     pub pipelines: Vec<PipelineHandle>,
+    /// The number of array layers rendered to, set from the pipelines in the pass
+    pub multiview: Option<NonZeroU32>,
 }
 
 impl RenderPass {
@@ -26,12 +32,18 @@ pub struct DepthAttachment {
     pub texture: TextureHandle,
     pub depth_op: Option<Operations<f32>>,
     pub stencil_op: Option<Operations<u32>>,
+    /// Pipelines in the pass can depth test against the texture but not write to it
+    pub read_only: bool,
+    /// A single sampled texture the multisampled depth is copied into after the pass
+    pub resolve_target: Option<TextureHandle>,
 }
 
 pub struct RenderPassBuilder<'a> {
     manager: &'a mut RenderManager,
     color_attachments: Vec<(TextureHandle, Operations<Color>)>,
+    color_resolve_targets: Vec<(usize, TextureHandle)>,
     depth_attachments: Option<DepthAttachment>,
+    depth_only: bool,
     name: Label<'a>,
     pipelines: Vec<PipelineHandle>,
 }
@@ -41,7 +53,9 @@ impl<'a> RenderPassBuilder<'a> {
         RenderPassBuilder {
             manager,
             color_attachments: Vec::new(),
+            color_resolve_targets: Vec::new(),
             depth_attachments: None,
+            depth_only: false,
             name,
             pipelines: Vec::new(),
         }
@@ -60,6 +74,41 @@ impl<'a> RenderPassBuilder<'a> {
         self
     }
 
+    /// Adds a color attachment with full control over how it is loaded and stored
+    pub fn add_color_attachment_ops(
+        mut self,
+        texture: TextureHandle,
+        ops: Operations<Color>,
+    ) -> RenderPassBuilder<'a> {
+        self.color_attachments.push((texture, ops));
+        self
+    }
+
+    /// Adds several color attachments at once, in the order they appear in the fragment shader's output
+    ///
+    /// This is useful for things like G-buffer passes where every target is cleared differently
+    pub fn add_color_attachments(
+        mut self,
+        attachments: impl IntoIterator<Item = (TextureHandle, Operations<Color>)>,
+    ) -> RenderPassBuilder<'a> {
+        self.color_attachments.extend(attachments);
+        self
+    }
+
+    /// Resolves the last color attachment added into `texture` at the end of the pass
+    ///
+    /// The attachment has to be multisampled and `texture` needs the same format and size
+    /// with one sample. `texture` can be [`FRAMEBUFFER`] to present an MSAA render.
+    pub fn resolve_color_to(mut self, texture: TextureHandle) -> RenderPassBuilder<'a> {
+        let index = self
+            .color_attachments
+            .len()
+            .checked_sub(1)
+            .expect("resolve_color_to needs a color attachment to be added first");
+        self.color_resolve_targets.push((index, texture));
+        self
+    }
+
     pub fn add_pipeline(mut self, pipeline: PipelineHandle) -> RenderPassBuilder<'a> {
         self.pipelines.push(pipeline);
         self
@@ -81,26 +130,178 @@ impl<'a> RenderPassBuilder<'a> {
                 load: clear.map(LoadOp::Clear).unwrap_or(LoadOp::Load),
                 store,
             }),
+            read_only: false,
+            resolve_target: None,
+        });
+        self
+    }
+
+    /// Adds a depth stencil attachment that is tested against but never cleared or written
+    ///
+    /// This is for passes that reuse the depth of an earlier pass, like a transparent pass
+    /// after an opaque one, and is needed if the texture is also sampled in the pass.<br>
+    /// Every pipeline in the pass needs `depth_write_enabled` off.
+    pub fn add_read_only_depth_attachment(mut self, texture: TextureHandle) -> Self {
+        self.depth_attachments = Some(DepthAttachment {
+            texture,
+            depth_op: None,
+            stencil_op: None,
+            read_only: true,
+            resolve_target: None,
         });
         self
     }
 
+    /// Copies the multisampled depth attachment into `texture` at the end of the pass
+    ///
+    /// This is for effects like SSAO that sample the depth of an MSAA pass. wgpu can't resolve
+    /// depth attachments itself so this is done with an extra draw that keeps the first sample
+    /// of each pixel. If that isn't accurate enough, render a separate depth prepass without
+    /// MSAA instead.<br>
+    /// The depth attachment has to be added first, be created with `.texture()`, and store its
+    /// depth. `texture` needs the same size and format with one sample and `.render()`.
+    pub fn resolve_depth_to(mut self, texture: TextureHandle) -> Self {
+        let depth = self
+            .depth_attachments
+            .as_mut()
+            .expect("resolve_depth_to needs a depth attachment to be added first");
+        depth.resolve_target = Some(texture);
+        self
+    }
+
+    /// Makes the pass only render to its depth attachment, like a shadow map pass
+    ///
+    /// Passes made of only depth only pipelines are detected automatically,
+    /// this is for passes that get their pipelines later with
+    /// [`RenderManager::add_pipeline_to_pass`](crate::manager::RenderManager::add_pipeline_to_pass).
+    pub fn depth_only(mut self) -> Self {
+        self.depth_only = true;
+        self
+    }
+
     pub fn build(mut self) -> RenderPassHandle {
-        // Assume that if no color attachments were added
-        // then we want to render just to the framebuffer
-        if self.color_attachments.is_empty() {
+        let pipelines_depth_only = self
+            .pipelines
+            .iter()
+            .map(|pipeline| self.manager.is_depth_only_pipeline(*pipeline))
+            .collect::<Vec<_>>();
+
+        if renders_to_framebuffer(
+            self.name,
+            self.depth_only,
+            !self.color_attachments.is_empty(),
+            self.depth_attachments.is_some(),
+            &pipelines_depth_only,
+        ) {
             self.color_attachments.push((FRAMEBUFFER, Operations {
                 load: LoadOp::Load,
                 store: true,
             }));
         }
 
+        let multiview = self.manager.pipelines_multiview(&self.pipelines);
+
+        for (index, target) in &self.color_resolve_targets {
+            self.manager.validate_color_resolve(
+                self.name,
+                self.color_attachments[*index].0,
+                *target,
+            );
+        }
+
+        if let Some(depth) = &self.depth_attachments {
+            if let Some(target) = depth.resolve_target {
+                assert!(
+                    multiview.is_none(),
+                    "Render pass {:?} uses multiview so its depth can't be resolved",
+                    self.name
+                );
+                self.manager.prepare_depth_resolve(
+                    self.name,
+                    depth.texture,
+                    depth.depth_op,
+                    target,
+                );
+            }
+        }
+
+        for pipeline in &self.pipelines {
+            self.manager.validate_pass_pipeline(
+                self.name,
+                &self.color_attachments,
+                self.depth_attachments.as_ref(),
+                multiview,
+                *pipeline,
+            );
+        }
 
         self.manager.add_render_pass(RenderPass {
             name: self.name.map(str::to_owned),
             color_attachments: self.color_attachments,
+            color_resolve_targets: self.color_resolve_targets,
             depth_attachments: self.depth_attachments,
             pipelines: self.pipelines,
+            multiview,
         })
     }
 }
+
+/// Whether a render pass needs the framebuffer added as its color attachment
+///
+/// Assume that if no color attachments were added then we want to render just to the framebuffer,
+/// unless the pass is marked depth only or has a depth attachment and only depth only pipelines.
+fn renders_to_framebuffer(
+    name: Label<'_>,
+    depth_only: bool,
+    has_color: bool,
+    has_depth: bool,
+    pipelines_depth_only: &[bool],
+) -> bool {
+    if depth_only {
+        assert!(
+            has_depth && !has_color,
+            "Depth only render pass {name:?} needs a depth attachment and no color attachments"
+        );
+    }
+
+    let depth_only = depth_only
+        || has_depth && !pipelines_depth_only.is_empty() && pipelines_depth_only.iter().all(|x| *x);
+
+    !has_color && !depth_only
+}
+
+#[cfg(test)]
+mod tests {
+    use super::renders_to_framebuffer;
+
+    #[test]
+    fn framebuffer_is_the_default_color_attachment() {
+        assert!(renders_to_framebuffer(None, false, false, false, &[]));
+        assert!(renders_to_framebuffer(None, false, false, true, &[false]));
+        // One color pipeline is enough to need somewhere to draw
+        let mixed = [true, false];
+        assert!(renders_to_framebuffer(None, false, false, true, &mixed));
+        assert!(renders_to_framebuffer(None, false, false, true, &[]));
+        assert!(!renders_to_framebuffer(None, false, true, true, &[false]));
+    }
+
+    #[test]
+    fn depth_only_passes_skip_the_framebuffer() {
+        let shadows = [true, true];
+        assert!(!renders_to_framebuffer(None, false, false, true, &shadows));
+        assert!(!renders_to_framebuffer(None, true, false, true, &[]));
+        assert!(!renders_to_framebuffer(None, true, false, true, &[false]));
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a depth attachment")]
+    fn depth_only_without_depth_panics() {
+        renders_to_framebuffer(Some("shadow"), true, false, false, &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no color attachments")]
+    fn depth_only_with_color_panics() {
+        renders_to_framebuffer(Some("shadow"), true, true, true, &[]);
+    }
+}
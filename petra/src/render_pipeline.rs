@@ -1,8 +1,16 @@
+use std::{num::NonZeroU32, ops::Range};
+
 use wgpu::{
+    BlendState,
+    BufferUsages,
+    ColorTargetState,
+    ColorWrites,
     CompareFunction,
     DepthBiasState,
     DepthStencilState,
+    Features,
     FragmentState,
+    IndexFormat,
     Label,
     MultisampleState,
     PipelineLayoutDescriptor,
@@ -10,17 +18,22 @@ use wgpu::{
     RenderPipeline as RawRenderPipeline,
     RenderPipelineDescriptor,
     StencilState,
+    TextureFormat,
+    VertexAttribute,
+    VertexBufferLayout,
     VertexState,
+    VertexStepMode,
 };
 pub use wgpu::{Face, FrontFace, PolygonMode, PrimitiveTopology};
 
 use crate::{
     bind_group::BindGroupHandle,
-    buffer::BufferHandle,
+    buffer::{Buffer, BufferHandle},
     handle::Handle,
     manager::RenderManager,
     shader::ShaderHandle,
     texture::TextureContents,
+    vertex::{vertex_format, Vertex},
 };
 
 pub type PipelineHandle = Handle<RenderPipeline>;
@@ -31,6 +44,54 @@ pub struct RenderPipeline {
     pub(crate) instance_buffers: Vec<BufferHandle>,
     pub(crate) bind_groups: Vec<BindGroupHandle>,
     pub(crate) index_buffers: Option<Handle<crate::buffer::Buffer>>,
+    pub(crate) name: Option<String>,
+    /// The format of each color target, empty for depth only pipelines
+    pub(crate) color_formats: Vec<TextureFormat>,
+    /// The layouts of the vertex buffers followed by the instance buffers
+    pub(crate) vertex_layouts: Vec<VertexBufferLayout<'static>>,
+    /// The first vertex buffer slot used by instance buffers
+    pub(crate) instance_slot_start: usize,
+    /// The range of indices to draw and the base vertex, draws the whole index buffer when `None`
+    pub(crate) index_draw: Option<(Range<u32>, i32)>,
+    pub(crate) multiview: Option<NonZeroU32>,
+    /// The buffer, offset, and count of indirect draw commands
+    pub(crate) multi_indirect: Option<(BufferHandle, u64, u32)>,
+    /// The number of vertices to draw without an index buffer, defaults to the length of the
+    /// vertex buffers or 1
+    pub(crate) vertex_count: Option<u32>,
+    /// The number of instances to draw, defaults to the length of the instance buffers or 1
+    pub(crate) instance_count: Option<u32>,
+    /// Whether the pipeline writes to the depth of its depth stencil attachment
+    pub(crate) depth_write: bool,
+    /// The number of samples every attachment it renders to needs
+    pub(crate) sample_count: u32,
+    /// Dynamic offsets used instead of the bind group's own offsets, by bind group index
+    pub(crate) dynamic_offsets: Vec<(usize, Vec<u32>)>,
+}
+
+/// The size of a `draw_indirect` command, 4 u32s
+pub(crate) const DRAW_INDIRECT_SIZE: u64 = 16;
+/// The size of a `draw_indexed_indirect` command, 5 u32s
+pub(crate) const DRAW_INDEXED_INDIRECT_SIZE: u64 = 20;
+
+impl RenderPipeline {
+    /// Checks that `buffer` can be used in vertex buffer slot `slot` of this pipeline
+    pub(crate) fn validate_vertex_buffer(&self, slot: usize, buffer: &Buffer) {
+        let layout = self.vertex_layouts.get(slot).unwrap_or_else(|| {
+            panic!(
+                "Pipeline {:?} only has {} vertex buffer slots, tried to use slot {slot}",
+                self.name,
+                self.vertex_layouts.len()
+            )
+        });
+
+        assert!(
+            buffer.vertex_format().as_ref() == Some(layout),
+            "Buffer {:?} doesn't match the vertex layout of slot {slot} in pipeline {:?}",
+            buffer.name(),
+            self.name
+        );
+    }
 }
 
 pub struct RenderPipelineBuilder<'a> {
@@ -43,12 +104,26 @@ pub struct RenderPipelineBuilder<'a> {
     culling: Option<Face>,
     polygon_mode: PolygonMode,
     vertex_buffers: Vec<BufferHandle>,
+    /// The shader location each vertex buffer's attributes start at, `None` keeps the buffer's own
+    vertex_location_bases: Vec<Option<u32>>,
     index_buffer: Option<BufferHandle>,
     instance_buffers: Vec<BufferHandle>,
+    vertex_layouts: Vec<VertexBufferLayout<'static>>,
+    instance_layouts: Vec<VertexBufferLayout<'static>>,
     bind_groups: Vec<BindGroupHandle>,
     depth_stencil: Option<DepthStencilState>,
     unclipped_depth: bool,
     conservative: bool,
+    index_draw: Option<(Range<u32>, i32)>,
+    multiview: Option<NonZeroU32>,
+    multi_indirect: Option<(BufferHandle, u64, u32)>,
+    strip_index_format: Option<Option<IndexFormat>>,
+    vertex_count: Option<u32>,
+    instance_count: Option<u32>,
+    color_format: Option<TextureFormat>,
+    blend: Option<BlendState>,
+    color_targets: Vec<ColorTargetState>,
+    sample_count: u32,
 }
 
 impl<'a> RenderPipelineBuilder<'a> {
@@ -63,12 +138,25 @@ impl<'a> RenderPipelineBuilder<'a> {
             culling: None,
             polygon_mode: PolygonMode::Fill,
             vertex_buffers: Vec::new(),
+            vertex_location_bases: Vec::new(),
             index_buffer: None,
             instance_buffers: Vec::new(),
+            vertex_layouts: Vec::new(),
+            instance_layouts: Vec::new(),
             bind_groups: Vec::new(),
             depth_stencil: None,
             unclipped_depth: false,
             conservative: false,
+            index_draw: None,
+            multiview: None,
+            multi_indirect: None,
+            strip_index_format: None,
+            vertex_count: None,
+            instance_count: None,
+            color_format: None,
+            blend: None,
+            color_targets: Vec::new(),
+            sample_count: 1,
         }
     }
 
@@ -77,6 +165,11 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    /// Sets the fragment shader, pipelines without one are depth only
+    ///
+    /// Depth only pipelines need a [`depth_stencil`](Self::depth_stencil) and write no colors.
+    /// They're used for shadow maps and depth prepasses, put them in a render pass
+    /// with a depth attachment and no color attachments.
     pub fn fragment_shader(mut self, shader: ShaderHandle, entry_point: &'a str) -> Self {
         self.fragment_shader = Some((entry_point, shader));
         self
@@ -87,6 +180,69 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    /// Sets the format of the color target, for pipelines that render to textures instead of the framebuffer
+    ///
+    /// Defaults to the surface's format.
+    /// This has to match the format of the color attachment of any pass the pipeline is used in.
+    pub fn color_target_format(mut self, format: TextureFormat) -> Self {
+        self.color_format = Some(format);
+        self
+    }
+
+    /// Blends the fragment shader's output with the color target instead of replacing it
+    ///
+    /// Pipelines replace the target's color by default.<br>
+    /// Whether colors are premultiplied by alpha is up to the shader and textures,
+    /// the blend state has to be chosen to match.
+    pub fn blend(mut self, blend: BlendState) -> Self {
+        self.blend = Some(blend);
+        self
+    }
+
+    /// Blends with [`BlendState::ALPHA_BLENDING`], for straight (not premultiplied) alpha
+    pub fn alpha_blending(self) -> Self {
+        self.blend(BlendState::ALPHA_BLENDING)
+    }
+
+    /// Adds a color target that `T` textures are rendered to, for writing several textures at once
+    ///
+    /// Targets are written in the order they're added, matching the fragment shader's
+    /// `@location`s and the color attachments of the pass.<br>
+    /// Once a target is added the pipeline no longer has the default target, so
+    /// [`color_target_format`](Self::color_target_format) and [`blend`](Self::blend) can't be used.
+    pub fn add_color_target<T: TextureContents>(
+        self,
+        blend: Option<BlendState>,
+        write_mask: ColorWrites,
+    ) -> Self {
+        self.add_color_target_format(T::FORMAT, blend, write_mask)
+    }
+
+    /// [`add_color_target`](Self::add_color_target) for a format without a [`TextureContents`],
+    /// like the surface's format
+    pub fn add_color_target_format(
+        mut self,
+        format: TextureFormat,
+        blend: Option<BlendState>,
+        write_mask: ColorWrites,
+    ) -> Self {
+        self.color_targets.push(ColorTargetState {
+            format,
+            blend,
+            write_mask,
+        });
+        self
+    }
+
+    /// Overrides the index format used for primitive restart in strip topologies
+    ///
+    /// By default this is inferred from the index buffer.
+    /// With a format set, an index of `0xFFFF` for u16 or `0xFFFFFFFF` for u32 starts a new strip.
+    pub fn strip_index_format(mut self, format: Option<IndexFormat>) -> Self {
+        self.strip_index_format = Some(format);
+        self
+    }
+
     pub fn polygon_mode(mut self, polygon_mode: PolygonMode) -> Self {
         self.polygon_mode = polygon_mode;
         self
@@ -104,6 +260,17 @@ impl<'a> RenderPipelineBuilder<'a> {
 
     pub fn add_vertex_buffer(mut self, buffer: BufferHandle) -> Self {
         self.vertex_buffers.push(buffer);
+        self.vertex_location_bases.push(None);
+        self
+    }
+
+    /// Adds a vertex buffer with its attributes moved to start at shader location `location_base`
+    ///
+    /// The attributes keep their order and spacing, only the locations are shifted.
+    /// This lets two vertex types that both start at location 0 be used in the same pipeline.
+    pub fn add_vertex_buffer_at(mut self, buffer: BufferHandle, location_base: u32) -> Self {
+        self.vertex_buffers.push(buffer);
+        self.vertex_location_bases.push(Some(location_base));
         self
     }
 
@@ -112,6 +279,25 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    /// Declares that the next vertex buffer slot holds `V`s
+    ///
+    /// This lets the pipeline be built before any buffers exist,
+    /// buffers can then be attached with [`RenderManager::set_pipeline_vertex_buffer`].<br>
+    /// If any layouts are declared, every vertex and instance buffer slot needs to be declared
+    /// and buffers added to the builder are checked against them.
+    pub fn vertex_layout<V: Vertex>(mut self) -> Self {
+        self.vertex_layouts
+            .push(vertex_format::<V>(VertexStepMode::Vertex));
+        self
+    }
+
+    /// Declares that the next instance buffer slot holds `V`s, see [`Self::vertex_layout`]
+    pub fn instance_layout<V: Vertex>(mut self) -> Self {
+        self.instance_layouts
+            .push(vertex_format::<V>(VertexStepMode::Instance));
+        self
+    }
+
     pub fn add_bind_group(mut self, bind_group: BindGroupHandle) -> Self {
         self.bind_groups.push(bind_group);
         self
@@ -145,6 +331,64 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    /// Only draw `index_range` of the index buffer, adding `base_vertex` to every index
+    ///
+    /// This allows drawing one mesh out of many packed into the same buffers
+    pub fn index_draw(mut self, index_range: Range<u32>, base_vertex: i32) -> Self {
+        self.index_draw = Some((index_range, base_vertex));
+        self
+    }
+
+    /// Render with `count` samples per pixel for MSAA
+    ///
+    /// Every attachment of the passes the pipeline is used in needs to be created with the same
+    /// [`sample_count`](crate::texture::TextureBuilder::sample_count).
+    pub fn sample_count(mut self, count: u32) -> Self {
+        self.sample_count = count;
+        self
+    }
+
+    /// Render every draw to `layers` array layers of the pass's attachments at once
+    ///
+    /// Requires `Features::MULTIVIEW` and attachments created with
+    /// [`size_2d_array`](crate::texture::TextureBuilder::size_2d_array) with at least `layers` layers
+    pub fn multiview(mut self, layers: NonZeroU32) -> Self {
+        self.multiview = Some(layers);
+        self
+    }
+
+    /// Draw `count` vertices, useful for vertices generated in the shader from `vertex_index`
+    /// like a fullscreen triangle
+    ///
+    /// Without this the pipeline draws as many vertices as its vertex buffers have, or one if it
+    /// has none. This can't be more than the length of the vertex buffers and can't be used with
+    /// an index buffer.
+    pub fn vertex_count(mut self, count: u32) -> Self {
+        self.vertex_count = Some(count);
+        self
+    }
+
+    /// Draw `count` instances, useful when the shader looks up per instance data with
+    /// `instance_index`, like from a storage buffer
+    ///
+    /// Without this the pipeline draws as many instances as its instance buffers have,
+    /// or one if it has none. This can't be more than the length of the instance buffers.
+    pub fn instance_count(mut self, count: u32) -> Self {
+        self.instance_count = Some(count);
+        self
+    }
+
+    /// Draw using `count` draw commands stored in `buffer` starting at `offset` bytes
+    ///
+    /// The commands are `draw_indexed_indirect` commands if the pipeline has an index buffer
+    /// and `draw_indirect` commands otherwise.<br>
+    /// All the commands are issued in one call when the device has `Features::MULTI_DRAW_INDIRECT`,
+    /// which is only supported on native. Otherwise each command is drawn separately.
+    pub fn multi_indirect(mut self, buffer: BufferHandle, offset: u64, count: u32) -> Self {
+        self.multi_indirect = Some((buffer, offset, count));
+        self
+    }
+
     pub fn conservative_rasterization(mut self) -> Self {
         self.conservative = true;
         self
@@ -174,18 +418,47 @@ impl<'a> RenderPipelineBuilder<'a> {
             .vertex_shader
             .expect("Vertex Shader not defined when building a render pipeline");
 
-        let formats = &[Some(self.manager.config.format.into())];
+        assert!(
+            self.fragment_shader.is_some() || self.depth_stencil.is_some(),
+            "Pipeline {:?} has no fragment shader or depth stencil so it doesn't write anything, \
+             depth only pipelines need a depth_stencil",
+            self.name
+        );
+
+        assert!(
+            self.color_targets.is_empty() || (self.color_format.is_none() && self.blend.is_none()),
+            "Pipeline {:?} uses add_color_target so it can't use color_target_format or blend, \
+             pass the format and blend to each add_color_target instead",
+            self.name
+        );
+
+        let color_targets = if self.fragment_shader.is_none() {
+            Vec::new()
+        } else if self.color_targets.is_empty() {
+            vec![ColorTargetState {
+                format: self.color_format.unwrap_or(self.manager.config.format),
+                blend: self.blend,
+                write_mask: ColorWrites::ALL,
+            }]
+        } else {
+            self.color_targets
+        };
+        let color_formats = color_targets
+            .iter()
+            .map(|target| target.format)
+            .collect::<Vec<_>>();
+        let formats = color_targets.into_iter().map(Some).collect::<Vec<_>>();
         let fragment_state = if let Some((entry_point, handle)) = self.fragment_shader {
             let module = &self
                 .manager
                 .get_shader(handle)
                 .expect("Invalid Shader Handle passed as a fragment shader")
-                .0;
+                .module;
 
             Some(FragmentState {
                 module,
                 entry_point,
-                targets: formats,
+                targets: &formats,
             })
         } else {
             None
@@ -195,7 +468,7 @@ impl<'a> RenderPipelineBuilder<'a> {
             .manager
             .get_shader(vert_shader)
             .expect("Invalid Shader Handle passed as a vertex shader")
-            .0;
+            .module;
 
         let mut vertex_buffers = Vec::with_capacity(self.vertex_buffers.len());
 
@@ -231,6 +504,197 @@ impl<'a> RenderPipelineBuilder<'a> {
             }));
         }
 
+        let mut instance_slot_start = self.vertex_buffers.len();
+
+        if !self.vertex_layouts.is_empty() || !self.instance_layouts.is_empty() {
+            assert!(
+                self.vertex_buffers.len() <= self.vertex_layouts.len()
+                    && self.instance_buffers.len() <= self.instance_layouts.len(),
+                "Pipeline {:?} has more buffers attached than vertex layouts declared",
+                self.name
+            );
+
+            let declared = self
+                .vertex_layouts
+                .iter()
+                .chain(&self.instance_layouts)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let instance_layouts = &declared[self.vertex_layouts.len() ..];
+            let (vertex_buffer_layouts, instance_buffer_layouts) =
+                vertex_buffers.split_at(self.vertex_buffers.len());
+
+            for (slot, (layout, buffer_layout)) in self
+                .vertex_layouts
+                .iter()
+                .zip(vertex_buffer_layouts)
+                .chain(instance_layouts.iter().zip(instance_buffer_layouts))
+                .enumerate()
+            {
+                assert!(
+                    layout == buffer_layout,
+                    "Buffer number {slot} attached to pipeline {:?} doesn't match its declared \
+                     vertex layout",
+                    self.name
+                );
+            }
+
+            instance_slot_start = self.vertex_layouts.len();
+            vertex_buffers = declared;
+        }
+
+        // The unshifted layouts are kept on the pipeline to check buffers against,
+        // the shifted attributes are only needed to create the pipeline
+        let attributes = vertex_buffers
+            .iter()
+            .enumerate()
+            .map(
+                |(slot, layout)| match self.vertex_location_bases.get(slot) {
+                    Some(Some(base)) => {
+                        let first = layout
+                            .attributes
+                            .iter()
+                            .map(|attribute| attribute.shader_location)
+                            .min()
+                            .unwrap_or(0);
+
+                        layout
+                            .attributes
+                            .iter()
+                            .map(|attribute| VertexAttribute {
+                                shader_location: attribute.shader_location - first + base,
+                                ..*attribute
+                            })
+                            .collect()
+                    }
+                    _ => layout.attributes.to_vec(),
+                },
+            )
+            .collect::<Vec<Vec<_>>>();
+
+        let mut used_locations = Vec::new();
+        for (slot, attributes) in attributes.iter().enumerate() {
+            for attribute in attributes {
+                assert!(
+                    !used_locations.contains(&attribute.shader_location),
+                    "Vertex buffer slot {slot} of pipeline {:?} uses shader location {} which is \
+                     already used by another vertex buffer",
+                    self.name,
+                    attribute.shader_location
+                );
+                used_locations.push(attribute.shader_location);
+            }
+        }
+
+        let pipeline_buffers = vertex_buffers
+            .iter()
+            .zip(&attributes)
+            .map(|(layout, attributes)| VertexBufferLayout {
+                array_stride: layout.array_stride,
+                step_mode: layout.step_mode,
+                attributes,
+            })
+            .collect::<Vec<_>>();
+
+        assert!(
+            self.vertex_count.is_none() || self.index_buffer.is_none(),
+            "vertex_count was set on render pipeline {:?} which has an index buffer, use \
+             index_draw instead",
+            self.name
+        );
+
+        if let Some((range, _)) = &self.index_draw {
+            let index_buffer = self
+                .index_buffer
+                .expect("index_draw was set on a render pipeline without an index buffer");
+            let len = self.manager.get_buffer(index_buffer).unwrap().len();
+
+            assert!(
+                range.end as u64 <= len,
+                "index_draw range {range:?} is outside of the index buffer of length {len}"
+            );
+        }
+
+        let topology = self
+            .topology
+            .expect("Topology not defined when building render pipeline");
+        let index_format = self.index_buffer.map(|buffer| {
+            let buffer = self
+                .manager
+                .get_buffer(buffer)
+                .expect("Invalid BufferHandle passed as an index buffer");
+
+            buffer.index_format().unwrap_or_else(|| {
+                panic!(
+                    "Index buffer {:?} of pipeline {:?} has to hold u16s or u32s",
+                    buffer.name(),
+                    self.name
+                )
+            })
+        });
+        let strip_index_format =
+            strip_index_format(self.name, topology, index_format, self.strip_index_format);
+
+        if let Some(count) = self.instance_count {
+            for handle in &self.instance_buffers {
+                let len = self
+                    .manager
+                    .get_buffer(*handle)
+                    .expect("Invalid Buffer Handle passed as an instance buffer")
+                    .len();
+
+                assert!(
+                    count as u64 <= len,
+                    "Pipeline {:?} draws {count} instances but has an instance buffer of length \
+                     {len}",
+                    self.name
+                );
+            }
+        }
+
+        if let Some((buffer, offset, count)) = self.multi_indirect {
+            let buffer = self
+                .manager
+                .get_buffer(buffer)
+                .expect("Invalid BufferHandle passed as an indirect buffer");
+            buffer.validate_usages(BufferUsages::INDIRECT, "be used for indirect draws");
+
+            let command_size = if self.index_buffer.is_some() {
+                DRAW_INDEXED_INDIRECT_SIZE
+            } else {
+                DRAW_INDIRECT_SIZE
+            };
+            let required = offset + count as u64 * command_size;
+
+            assert!(
+                offset.is_multiple_of(4),
+                "Indirect buffer offset {offset} must be a multiple of 4"
+            );
+            assert!(
+                required <= buffer.inner().size(),
+                "Indirect buffer {:?} has {} bytes but pipeline {:?} needs {required} bytes for \
+                 {count} draw commands",
+                buffer.name(),
+                buffer.inner().size(),
+                self.name
+            );
+        }
+
+        if self.multiview.is_some() {
+            assert!(
+                self.manager.device.features().contains(Features::MULTIVIEW),
+                "Pipeline {:?} uses multiview but the device wasn't created with \
+                 Features::MULTIVIEW, request it in RenderManagerDescriptor::features",
+                self.name
+            );
+        }
+
+        let depth_write = self
+            .depth_stencil
+            .as_ref()
+            .is_some_and(|state| state.depth_write_enabled);
+
         let pipeline = self
             .manager
             .device
@@ -240,20 +704,11 @@ impl<'a> RenderPipelineBuilder<'a> {
                 vertex: VertexState {
                     module: vert_shader,
                     entry_point: vert_entry_point,
-                    buffers: &vertex_buffers,
+                    buffers: &pipeline_buffers,
                 },
                 primitive: PrimitiveState {
-                    topology: self
-                        .topology
-                        .expect("Topology not defined when building render pipeline"),
-                    strip_index_format: if self.topology.unwrap().is_strip()
-                        && self.index_buffer.is_some()
-                    {
-                        let buffer = self.index_buffer.unwrap();
-                        self.manager.get_buffer(buffer).unwrap().index_format()
-                    } else {
-                        None
-                    },
+                    topology,
+                    strip_index_format,
                     front_face: self
                         .front_face
                         .expect("Front face not defined when building render pipeline"),
@@ -263,9 +718,12 @@ impl<'a> RenderPipelineBuilder<'a> {
                     conservative: self.conservative,
                 },
                 depth_stencil: self.depth_stencil,
-                multisample: MultisampleState::default(),
+                multisample: MultisampleState {
+                    count: self.sample_count,
+                    ..Default::default()
+                },
                 fragment: fragment_state,
-                multiview: None,
+                multiview: self.multiview,
             });
 
         let pipeline = RenderPipeline {
@@ -274,8 +732,94 @@ impl<'a> RenderPipelineBuilder<'a> {
             instance_buffers: self.instance_buffers,
             index_buffers: self.index_buffer,
             bind_groups: self.bind_groups,
+            name: self.name.map(str::to_owned),
+            color_formats,
+            vertex_layouts: vertex_buffers,
+            instance_slot_start,
+            index_draw: self.index_draw,
+            multiview: self.multiview,
+            multi_indirect: self.multi_indirect,
+            vertex_count: self.vertex_count,
+            instance_count: self.instance_count,
+            depth_write,
+            sample_count: self.sample_count,
+            dynamic_offsets: Vec::new(),
         };
 
         self.manager.add_render_pipeline(pipeline)
     }
 }
+
+/// Strips always restart on the attached index buffer's format unless `requested` overrides it
+fn strip_index_format(
+    name: Label<'_>,
+    topology: PrimitiveTopology,
+    index_format: Option<IndexFormat>,
+    requested: Option<Option<IndexFormat>>,
+) -> Option<IndexFormat> {
+    let inferred = index_format.filter(|_| topology.is_strip());
+
+    match requested {
+        Some(format) => {
+            assert!(
+                topology.is_strip(),
+                "strip_index_format was set on pipeline {name:?} which doesn't use a strip \
+                 topology",
+            );
+            assert!(
+                format.is_none() || inferred.is_none() || format == inferred,
+                "strip_index_format {format:?} on pipeline {name:?} doesn't match the index \
+                 buffer format {inferred:?}",
+            );
+            format
+        }
+        None => inferred,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_use_the_index_buffer_format() {
+        let strip = PrimitiveTopology::TriangleStrip;
+        let list = PrimitiveTopology::TriangleList;
+
+        assert_eq!(
+            strip_index_format(None, strip, Some(IndexFormat::Uint16), None),
+            Some(IndexFormat::Uint16)
+        );
+        assert_eq!(
+            strip_index_format(None, list, Some(IndexFormat::Uint16), None),
+            None
+        );
+        assert_eq!(strip_index_format(None, strip, None, None), None);
+        assert_eq!(
+            strip_index_format(None, strip, Some(IndexFormat::Uint32), Some(None)),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match the index buffer format")]
+    fn mismatched_strip_format_panics() {
+        strip_index_format(
+            None,
+            PrimitiveTopology::LineStrip,
+            Some(IndexFormat::Uint16),
+            Some(Some(IndexFormat::Uint32)),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't use a strip topology")]
+    fn strip_format_on_a_list_panics() {
+        strip_index_format(
+            None,
+            PrimitiveTopology::TriangleList,
+            None,
+            Some(Some(IndexFormat::Uint16)),
+        );
+    }
+}
@@ -6,6 +6,7 @@ use wgpu::{
     FilterMode,
     Label,
     Sampler,
+    SamplerBindingType,
     SamplerBorderColor,
     SamplerDescriptor,
 };
@@ -15,13 +16,43 @@ use crate::{handle::Handle, manager::RenderManager};
 pub type TextureSampleHandle = Handle<TextureSampler>;
 
 pub struct TextureSampler {
+    name: Option<String>,
     sampler: Sampler,
+    compare: Option<CompareFunction>,
+    filtering: bool,
 }
 
 impl TextureSampler {
     pub(crate) fn inner(&self) -> &Sampler {
         &self.sampler
     }
+
+    /// Panics if the sampler can't be bound to a binding of type `kind`
+    ///
+    /// Comparison samplers have to use [`SamplerBindingType::Comparison`] and the other types
+    /// can't be comparison samplers, [`SamplerBindingType::NonFiltering`] also requires nearest filters.
+    pub(crate) fn validate_binding_type(&self, binding: u32, kind: SamplerBindingType) {
+        let name = &self.name;
+
+        match kind {
+            SamplerBindingType::Comparison => assert!(
+                self.compare.is_some(),
+                "Sampler {name:?} is bound at {binding} as a comparison sampler but has no \
+                 compare function, use .compare() or .shadow() when building it"
+            ),
+            SamplerBindingType::Filtering | SamplerBindingType::NonFiltering => assert!(
+                self.compare.is_none(),
+                "Sampler {name:?} is a comparison sampler so it must be bound at {binding} with \
+                 SamplerBindingType::Comparison"
+            ),
+        }
+
+        assert!(
+            kind != SamplerBindingType::NonFiltering || !self.filtering,
+            "Sampler {name:?} uses linear filtering so it can't be bound at {binding} with \
+             SamplerBindingType::NonFiltering"
+        );
+    }
 }
 
 pub struct TextureSamplerBuilder<'a> {
@@ -118,6 +149,20 @@ impl<'a> TextureSamplerBuilder<'a> {
         self
     }
 
+    /// Sets up a sampler for sampling shadow maps with hardware PCF
+    ///
+    /// This uses linear filtering, clamps to the edge of the texture, and compares with `LessEqual`.<br>
+    /// It has to be bound with [`SamplerBindingType::Comparison`] and used with a depth texture
+    /// bound with `TextureSampleType::Depth`, sampled with `textureSampleCompare` in the shader.
+    pub fn shadow(self) -> Self {
+        self.address_mode_u(AddressMode::ClampToEdge)
+            .address_mode_v(AddressMode::ClampToEdge)
+            .address_mode_w(AddressMode::ClampToEdge)
+            .mag_filter(FilterMode::Linear)
+            .min_filter(FilterMode::Linear)
+            .compare(CompareFunction::LessEqual)
+    }
+
     pub fn anisotropy_clamp(mut self, val: u8) -> Self {
         self.anisotropy_clamp = NonZeroU8::new(val);
         self
@@ -129,7 +174,13 @@ impl<'a> TextureSamplerBuilder<'a> {
     }
 
     pub fn build(self) -> TextureSampleHandle {
+        let filtering =
+            [self.mag_filter, self.min_filter, self.mipmap_filter].contains(&FilterMode::Linear);
+
         self.manager.add_sampler(TextureSampler {
+            name: self.name.map(str::to_owned),
+            compare: self.compare,
+            filtering,
             sampler: self.manager.device.create_sampler(&SamplerDescriptor {
                 label: self.name,
                 address_mode_u: self.address_mode_u,
@@ -1,7 +1,116 @@
+use std::collections::HashMap;
+
+use naga::{
+    valid::{Capabilities, ValidationFlags, Validator},
+    Module,
+    ShaderStage,
+};
 use wgpu::ShaderModule;
 
 use crate::handle::Handle;
 
 pub type ShaderHandle = Handle<Shader>;
 
-pub struct Shader(pub(crate) ShaderModule);
+pub struct Shader {
+    pub(crate) module: ShaderModule,
+    /// The bind group indices each entry point uses, `None` if the source couldn't be reflected
+    entry_point_groups: Option<HashMap<String, Vec<u32>>>,
+    /// The `@workgroup_size` of each compute entry point
+    workgroup_sizes: HashMap<String, [u32; 3]>,
+}
+
+impl Shader {
+    pub(crate) fn new(module: ShaderModule, source: &str) -> Shader {
+        let parsed = naga::front::wgsl::parse_str(source).ok();
+
+        Shader {
+            module,
+            entry_point_groups: parsed.as_ref().and_then(reflect_bind_groups),
+            workgroup_sizes: parsed
+                .as_ref()
+                .map(reflect_workgroup_sizes)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The sorted bind group indices used by `entry_point`, including through functions it calls
+    pub(crate) fn entry_point_groups(&self, entry_point: &str) -> Option<&[u32]> {
+        self.entry_point_groups
+            .as_ref()?
+            .get(entry_point)
+            .map(Vec::as_slice)
+    }
+
+    /// The `@workgroup_size` declared by the compute entry point `entry_point`
+    pub(crate) fn workgroup_size(&self, entry_point: &str) -> Option<[u32; 3]> {
+        self.workgroup_sizes.get(entry_point).copied()
+    }
+}
+
+fn reflect_bind_groups(module: &Module) -> Option<HashMap<String, Vec<u32>>> {
+    // wgpu already validates the shader, this is only for finding which globals are used
+    let info = Validator::new(ValidationFlags::empty(), Capabilities::all())
+        .validate(module)
+        .ok()?;
+
+    let groups = module
+        .entry_points
+        .iter()
+        .enumerate()
+        .map(|(i, entry_point)| {
+            let entry_info = info.get_entry_point(i);
+
+            let mut groups = module
+                .global_variables
+                .iter()
+                .filter(|(handle, _)| !entry_info[*handle].is_empty())
+                .filter_map(|(_, global)| global.binding.as_ref().map(|binding| binding.group))
+                .collect::<Vec<_>>();
+            groups.sort_unstable();
+            groups.dedup();
+
+            (entry_point.name.clone(), groups)
+        })
+        .collect();
+
+    Some(groups)
+}
+
+fn reflect_workgroup_sizes(module: &Module) -> HashMap<String, [u32; 3]> {
+    module
+        .entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == ShaderStage::Compute)
+        .map(|entry_point| (entry_point.name.clone(), entry_point.workgroup_size))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflects_compute_workgroup_sizes() {
+        let module = naga::front::wgsl::parse_str(
+            "
+            @compute @workgroup_size(8, 8, 1)
+            fn main() {}
+
+            @compute @workgroup_size(64)
+            fn line() {}
+
+            @vertex
+            fn vs_main() -> @builtin(position) vec4<f32> {
+                return vec4<f32>(0.0);
+            }
+            ",
+        )
+        .unwrap();
+
+        let sizes = reflect_workgroup_sizes(&module);
+
+        assert_eq!(sizes.get("main"), Some(&[8, 8, 1]));
+        assert_eq!(sizes.get("line"), Some(&[64, 1, 1]));
+        assert_eq!(sizes.get("vs_main"), None);
+    }
+}
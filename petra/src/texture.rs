@@ -1,20 +1,42 @@
-use std::{any::TypeId, marker::PhantomData, num::NonZeroU32, sync::Arc};
+use std::{
+    any::TypeId,
+    marker::PhantomData,
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+        Arc,
+    },
+};
 
 use bytemuck::{Pod, Zeroable};
 use wgpu::{
+    BufferDescriptor,
+    BufferUsages,
+    CommandEncoderDescriptor,
     Device,
     Extent3d,
+    ImageCopyBuffer,
+    ImageCopyTexture,
     ImageDataLayout,
     Label,
+    Limits,
+    LoadOp,
+    Maintain,
+    MapMode,
+    Origin3d,
     Queue,
     SurfaceConfiguration,
     Texture as RawTexture,
+    TextureAspect,
     TextureDescriptor,
     TextureDimension,
     TextureFormat,
     TextureUsages,
     TextureView,
     TextureViewDescriptor,
+    TextureViewDimension,
+    COPY_BYTES_PER_ROW_ALIGNMENT,
 };
 
 use crate::{handle::Handle, manager::RenderManager};
@@ -31,6 +53,7 @@ pub struct Texture {
     mip_level_count: u32,
     sample_count: u32,
     data_type: TypeId,
+    initialized: AtomicBool,
 }
 
 impl Texture {
@@ -51,6 +74,16 @@ impl Texture {
         self.resize(TextureSize::D2(width, height), config)
     }
 
+    pub fn resize_2d_array(
+        &mut self,
+        width: u32,
+        height: u32,
+        layers: u32,
+        config: &SurfaceConfiguration,
+    ) {
+        self.resize(TextureSize::D2Array(width, height, layers), config)
+    }
+
     pub fn resize_3d(
         &mut self,
         width: u32,
@@ -61,19 +94,20 @@ impl Texture {
         self.resize(TextureSize::D3(width, height, depth_or_array_len), config)
     }
 
+    pub fn resize_cube(&mut self, size: u32, config: &SurfaceConfiguration) {
+        self.resize(TextureSize::Cube(size), config)
+    }
+
     pub fn write_data<T: TextureContents>(
         &mut self,
         data: &[T::Data],
         config: &SurfaceConfiguration,
     ) {
-        if TypeId::of::<T>() != self.data_type {
-            panic!(
-                "Tried to write to texture with a type that did not match the one it was declared \
-                 with"
-            )
-        }
+        self.validate_data_type::<T>();
+        self.validate_usages(TextureUsages::COPY_DST, "be written to");
 
         let byte_slice = bytemuck::cast_slice(data);
+        self.initialized.store(true, Ordering::Relaxed);
         self.queue.write_texture(
             self.texture.as_image_copy(),
             byte_slice,
@@ -88,6 +122,120 @@ impl Texture {
         );
     }
 
+    /// Writes `data` to a single layer of a 2d array texture
+    pub fn write_layer<T: TextureContents>(
+        &mut self,
+        layer: u32,
+        data: &[T::Data],
+        config: &SurfaceConfiguration,
+    ) {
+        self.validate_data_type::<T>();
+        self.validate_usages(TextureUsages::COPY_DST, "be written to");
+
+        let layers = self
+            .array_layers()
+            .expect("Tried to write a layer of a texture that isn't a 2d array or cube texture");
+        assert!(
+            layer < layers,
+            "Tried to write to layer {layer} of texture {:?} which only has {layers} layers",
+            self.name
+        );
+
+        let size = self.size.get_size(config);
+        self.initialized.store(true, Ordering::Relaxed);
+        self.queue.write_texture(
+            ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer,
+                },
+                aspect: TextureAspect::All,
+            },
+            bytemuck::cast_slice(data),
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: self
+                    .size
+                    .get_bytes_per_row(std::mem::size_of::<T::Data>() as u32, config),
+                rows_per_image: None,
+            },
+            Extent3d {
+                depth_or_array_layers: 1,
+                ..size
+            },
+        );
+    }
+
+    /// Copies the first mip level of every layer back to the cpu, blocking until the gpu is done
+    pub(crate) fn read_data<T: TextureContents>(&self) -> Vec<T::Data> {
+        self.validate_data_type::<T>();
+        self.validate_usages(TextureUsages::COPY_SRC, "be read back");
+
+        let size = self.texture.size();
+        let row_size = size.width * std::mem::size_of::<T::Data>() as u32;
+        let padded_row_size = padded_row_size(row_size);
+        let rows = size.height * size.depth_or_array_layers;
+
+        let buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("Texture Readback Buffer"),
+            size: padded_row_size as u64 * rows as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Texture Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_row_size),
+                    rows_per_image: NonZeroU32::new(size.height),
+                },
+            },
+            size,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        let slice = buffer.slice(..);
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(Maintain::Wait);
+        receiver
+            .recv()
+            .expect("Texture readback buffer was never mapped")
+            .unwrap_or_else(|e| panic!("Failed to read back texture {:?}: {e}", self.name));
+
+        let mut data = vec![T::Data::zeroed(); (size.width * rows) as usize];
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut data);
+        let view = slice.get_mapped_range();
+
+        strip_row_padding(&view, bytes, row_size, padded_row_size);
+
+        drop(view);
+        buffer.unmap();
+        data
+    }
+
+    fn validate_data_type<T: TextureContents>(&self) {
+        if TypeId::of::<T>() != self.data_type {
+            panic!(
+                "Tried to access texture with a type that did not match the one it was declared \
+                 with"
+            )
+        }
+    }
+
     fn resize(&mut self, size: TextureSize, config: &SurfaceConfiguration) {
         if let TextureSize::Surface | TextureSize::ScaledSurface(..) = size {
             panic!("Texture size can only be set to be relative to the surface size at creation");
@@ -95,7 +243,10 @@ impl Texture {
             self.size = match (self.size, size) {
                 (TextureSize::D1(_), TextureSize::D1(x)) => TextureSize::D1(x),
                 (TextureSize::D2(..), TextureSize::D2(x, y)) => TextureSize::D2(x, y),
+                (TextureSize::D2Array(..), TextureSize::D2Array(x, y, z)) =>
+                    TextureSize::D2Array(x, y, z),
                 (TextureSize::D3(..), TextureSize::D3(x, y, z)) => TextureSize::D3(x, y, z),
+                (TextureSize::Cube(_), TextureSize::Cube(x)) => TextureSize::Cube(x),
                 _ => panic!(
                     "Tried to resize a texture to be a different dimension that it was declared as"
                 ),
@@ -106,6 +257,9 @@ impl Texture {
     }
 
     fn recreate(&mut self, size: Extent3d) {
+        self.size
+            .validate_limits(self.name.as_deref(), size, &self.device.limits());
+
         let format = self.texture.format();
         let usage = self.texture.usage();
         let old_texture = std::mem::replace(
@@ -123,16 +277,181 @@ impl Texture {
         );
 
         old_texture.destroy();
+        self.initialized.store(false, Ordering::Relaxed);
+    }
+
+    /// Replaces a [`LoadOp::Load`] with a clear if nothing has been written to the texture yet
+    ///
+    /// A texture's contents are undefined until something is written to it,
+    /// so loading it on the first frame (or after a resize) would read garbage.
+    pub(crate) fn initialized_load_op<V>(&self, load: LoadOp<V>, clear_value: V) -> LoadOp<V> {
+        match load {
+            LoadOp::Load if !self.initialized.load(Ordering::Relaxed) => LoadOp::Clear(clear_value),
+            load => load,
+        }
+    }
+
+    /// Records that a pass has written to the texture, if the pass actually stores its results
+    pub(crate) fn mark_initialized(&self, stored: bool) {
+        if stored {
+            self.initialized.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn usages(&self) -> TextureUsages {
+        self.texture.usage()
+    }
+
+    /// Panics naming the missing [`TextureBuilder`] methods if the texture lacks any of `required`
+    ///
+    /// `action` should finish the sentence "texture 'X' must be created with .foo() to ..."
+    pub(crate) fn validate_usages(&self, required: TextureUsages, action: &str) {
+        const BUILDER_METHODS: &[(TextureUsages, &str)] = &[
+            (TextureUsages::COPY_SRC, ".copy_src()"),
+            (TextureUsages::COPY_DST, ".copy_dst()"),
+            (TextureUsages::TEXTURE_BINDING, ".texture()"),
+            (TextureUsages::STORAGE_BINDING, ".storage()"),
+            (TextureUsages::RENDER_ATTACHMENT, ".render()"),
+        ];
+
+        let missing = required - self.usages();
+
+        if !missing.is_empty() {
+            let methods = BUILDER_METHODS
+                .iter()
+                .filter(|(usage, _)| missing.contains(*usage))
+                .map(|(_, method)| *method)
+                .collect::<Vec<_>>()
+                .join(" and ");
+            panic!(
+                "texture '{}' must be created with {methods} to {action}",
+                self.name.as_deref().unwrap_or("unnamed")
+            );
+        }
+    }
+
+    pub(crate) fn destroy(&self) {
+        self.texture.destroy();
     }
 
     pub(crate) fn format(&self) -> TextureFormat {
         self.texture.format()
     }
 
+    pub(crate) fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub(crate) fn extent(&self) -> Extent3d {
+        self.texture.size()
+    }
+
+    /// Returns true if this is a single layer, single sample 2d texture
+    pub(crate) fn is_plain_2d(&self) -> bool {
+        self.texture.dimension() == TextureDimension::D2
+            && self.array_layers().is_none()
+            && self.sample_count == 1
+    }
+
+    /// The number of array layers if this is a 2d array or cube texture
+    pub(crate) fn array_layers(&self) -> Option<u32> {
+        match self.size {
+            TextureSize::D2Array(_, _, layers) => Some(layers),
+            TextureSize::Cube(_) => Some(6),
+            _ => None,
+        }
+    }
+
+    /// The dimension of the view made when the texture is bound without asking for a different one
+    pub(crate) fn view_dimension(&self) -> TextureViewDimension {
+        match self.size {
+            TextureSize::D1(_) => TextureViewDimension::D1,
+            TextureSize::D2(..) | TextureSize::Surface | TextureSize::ScaledSurface(..) =>
+                TextureViewDimension::D2,
+            TextureSize::D2Array(..) => TextureViewDimension::D2Array,
+            TextureSize::D3(..) => TextureViewDimension::D3,
+            TextureSize::Cube(_) => TextureViewDimension::Cube,
+        }
+    }
+
+    /// Panics if a view of every layer of the texture can't have the dimension `dimension`
+    ///
+    /// 2d textures can be viewed as a 1 layer array, arrays with a multiple of 6 layers
+    /// can be viewed as cubes, and cubes can be viewed as 6 layer arrays,
+    /// otherwise the dimension has to match the texture.
+    pub(crate) fn validate_view_dimension(&self, binding: u32, dimension: TextureViewDimension) {
+        let natural = self.view_dimension();
+        let layers = self.array_layers().unwrap_or(1);
+
+        let compatible = match dimension {
+            _ if dimension == natural => true,
+            TextureViewDimension::D2Array if natural == TextureViewDimension::Cube => true,
+            TextureViewDimension::D2 | TextureViewDimension::D2Array =>
+                natural == TextureViewDimension::D2
+                    || (natural == TextureViewDimension::D2Array && layers == 1),
+            TextureViewDimension::Cube => layers == 6,
+            TextureViewDimension::CubeArray => layers.is_multiple_of(6),
+            _ => false,
+        };
+
+        assert!(
+            compatible,
+            "Texture {:?} is bound at {binding} with view dimension {dimension:?} but it is a \
+             {natural:?} texture with {layers} layer(s)",
+            self.name
+        );
+    }
+
+    /// Creates a view of every layer of the texture with the dimension `dimension`
+    pub(crate) fn get_view_with_dimension(&self, dimension: TextureViewDimension) -> TextureView {
+        if dimension == self.view_dimension() {
+            self.get_view()
+        } else {
+            self.texture.create_view(&TextureViewDescriptor {
+                dimension: Some(dimension),
+                ..Default::default()
+            })
+        }
+    }
+
     pub(crate) fn get_view(&self) -> TextureView {
         // I really don't know if using anything but the defaults has any use
         // I really don't want to make this configurable
-        self.texture.create_view(&TextureViewDescriptor::default())
+        if let TextureSize::Cube(_) = self.size {
+            self.texture.create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::Cube),
+                ..Default::default()
+            })
+        } else if let Some(layers) = self.array_layers() {
+            self.get_array_view(layers)
+        } else {
+            self.texture.create_view(&TextureViewDescriptor::default())
+        }
+    }
+
+    /// Creates a view of only the depth of a depth stencil texture, for sampling it in a shader
+    pub(crate) fn get_depth_view(&self) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor {
+            aspect: TextureAspect::DepthOnly,
+            ..Default::default()
+        })
+    }
+
+    /// Gets the view used as an attachment in a render pass with the given multiview
+    pub(crate) fn get_pass_view(&self, multiview: Option<NonZeroU32>) -> TextureView {
+        match multiview {
+            Some(layers) => self.get_array_view(layers.get()),
+            None => self.get_view(),
+        }
+    }
+
+    /// Creates a 2d array view of the first `layers` layers of the texture
+    pub(crate) fn get_array_view(&self, layers: u32) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            array_layer_count: NonZeroU32::new(layers),
+            ..Default::default()
+        })
     }
 }
 pub struct TextureBuilder<'a, T: TextureContents> {
@@ -169,11 +488,30 @@ impl<'a, T: TextureContents> TextureBuilder<'a, T> {
         self
     }
 
+    /// Makes the texture a 2d texture array with `layers` layers
+    ///
+    /// Unlike [`size_3d`](Self::size_3d) each layer is a separate 2d image,
+    /// bind it with `TextureViewDimension::D2Array`.
+    pub fn size_2d_array(mut self, width: u32, height: u32, layers: u32) -> Self {
+        self.size = Some(TextureSize::D2Array(width, height, layers));
+        self
+    }
+
     pub fn size_3d(mut self, width: u32, height: u32, depth_or_array_len: u32) -> Self {
         self.size = Some(TextureSize::D3(width, height, depth_or_array_len));
         self
     }
 
+    /// Makes the texture a cubemap with 6 `size` by `size` faces, for skyboxes and reflections
+    ///
+    /// It's bound with `TextureViewDimension::Cube` and each face can be written with
+    /// [`write_to_texture_layer`](crate::manager::RenderManager::write_to_texture_layer)
+    /// in the order +X, -X, +Y, -Y, +Z, -Z.
+    pub fn size_cube(mut self, size: u32) -> Self {
+        self.size = Some(TextureSize::Cube(size));
+        self
+    }
+
     pub fn size_framebuffer(mut self) -> Self {
         self.size = Some(TextureSize::Surface);
         self
@@ -184,6 +522,22 @@ impl<'a, T: TextureContents> TextureBuilder<'a, T> {
         self
     }
 
+    /// Makes the texture multisampled with `count` samples per pixel, for MSAA render targets
+    ///
+    /// Pipelines rendering to it need the same [`sample_count`](crate::render_pipeline::RenderPipelineBuilder::sample_count).
+    /// Multisampled textures can't be copied or sampled normally, resolve them into a single
+    /// sampled texture with [`resolve_color_to`](crate::render_pass::RenderPassBuilder::resolve_color_to).
+    pub fn sample_count(mut self, count: u32) -> Self {
+        self.sample_count = count;
+        self
+    }
+
+    /// Adds arbitrary usages to the texture
+    pub fn usage(mut self, usage: TextureUsages) -> Self {
+        self.usage |= usage;
+        self
+    }
+
     pub fn copy_src(mut self) -> Self {
         self.usage |= TextureUsages::COPY_SRC;
         self
@@ -210,13 +564,33 @@ impl<'a, T: TextureContents> TextureBuilder<'a, T> {
     }
 
     pub fn build(self) -> TextureHandle {
+        let texture = self.create_texture();
+        self.manager.add_texture(texture)
+    }
+
+    /// Builds the texture and uploads `data` to it
+    ///
+    /// This adds the usages needed to upload and sample the texture,
+    /// `data` should be tightly packed rows of texels for the whole texture.
+    pub fn build_init(mut self, data: &[T::Data]) -> TextureHandle {
+        self.usage |= TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING;
+
+        let mut texture = self.create_texture();
+        texture.write_data::<T>(data, &self.manager.config);
+        self.manager.add_texture(texture)
+    }
+
+    fn create_texture(&self) -> Texture {
         let size = self
             .size
             .expect("Trying to build texture with no specified size");
 
+        let extent = size.get_size(&self.manager.config);
+        size.validate_limits(self.label, extent, &self.manager.device.limits());
+
         let texture = self.manager.device.create_texture(&TextureDescriptor {
             label: self.label,
-            size: size.get_size(&self.manager.config),
+            size: extent,
             mip_level_count: self.mip_level_count,
             sample_count: self.sample_count,
             dimension: size.get_dimension(),
@@ -226,7 +600,7 @@ impl<'a, T: TextureContents> TextureBuilder<'a, T> {
             view_formats: &[],
         });
 
-        self.manager.add_texture(Texture {
+        Texture {
             name: self.label.map(|s| s.to_owned()),
             texture,
             device: self.manager.device.clone(),
@@ -235,7 +609,23 @@ impl<'a, T: TextureContents> TextureBuilder<'a, T> {
             mip_level_count: self.mip_level_count,
             sample_count: self.sample_count,
             data_type: TypeId::of::<T>(),
-        })
+            initialized: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Rows copied into a buffer have to start on a 256 byte boundary
+fn padded_row_size(row_size: u32) -> u32 {
+    row_size.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+/// Copies each `row_size` row out of `padded` into `rows`, dropping the padding after it
+fn strip_row_padding(padded: &[u8], rows: &mut [u8], row_size: u32, padded_row_size: u32) {
+    for (row, padded_row) in rows
+        .chunks_exact_mut(row_size as usize)
+        .zip(padded.chunks_exact(padded_row_size as usize))
+    {
+        row.copy_from_slice(&padded_row[.. row_size as usize]);
     }
 }
 
@@ -243,7 +633,9 @@ impl<'a, T: TextureContents> TextureBuilder<'a, T> {
 enum TextureSize {
     D1(u32),
     D2(u32, u32),
+    D2Array(u32, u32, u32),
     D3(u32, u32, u32),
+    Cube(u32),
     Surface,
     ScaledSurface(f32, f32),
 }
@@ -261,7 +653,12 @@ impl TextureSize {
                 height: *y,
                 depth_or_array_layers: 1,
             },
-            TextureSize::D3(x, y, z) => Extent3d {
+            TextureSize::Cube(x) => Extent3d {
+                width: *x,
+                height: *x,
+                depth_or_array_layers: 6,
+            },
+            TextureSize::D2Array(x, y, z) | TextureSize::D3(x, y, z) => Extent3d {
                 width: *x,
                 height: *y,
                 depth_or_array_layers: *z,
@@ -279,11 +676,43 @@ impl TextureSize {
         }
     }
 
+    /// Panics with the requested and maximum size if `extent` is larger than the device supports
+    fn validate_limits(&self, label: Label, extent: Extent3d, limits: &Limits) {
+        let (dimension, max) = match self.get_dimension() {
+            TextureDimension::D1 => ("1d", limits.max_texture_dimension_1d),
+            TextureDimension::D2 => ("2d", limits.max_texture_dimension_2d),
+            TextureDimension::D3 => ("3d", limits.max_texture_dimension_3d),
+        };
+
+        let max_depth = match self {
+            TextureSize::D3(..) => max,
+            _ => limits.max_texture_array_layers,
+        };
+
+        assert!(
+            extent.width <= max && extent.height <= max,
+            "Texture {label:?} was requested with size {}x{} but the device supports {dimension} \
+             textures up to {max}x{max}",
+            extent.width,
+            extent.height,
+        );
+
+        assert!(
+            extent.depth_or_array_layers <= max_depth,
+            "Texture {label:?} was requested with a depth or array length of {} but the device \
+             supports up to {max_depth}",
+            extent.depth_or_array_layers,
+        );
+    }
+
     pub fn get_dimension(&self) -> TextureDimension {
         match &self {
             TextureSize::D1(_) => TextureDimension::D1,
-            TextureSize::D2(..) | TextureSize::Surface | TextureSize::ScaledSurface(..) =>
-                TextureDimension::D2,
+            TextureSize::D2(..)
+            | TextureSize::D2Array(..)
+            | TextureSize::Cube(_)
+            | TextureSize::Surface
+            | TextureSize::ScaledSurface(..) => TextureDimension::D2,
             TextureSize::D3(..) => TextureDimension::D3,
         }
     }
@@ -296,7 +725,8 @@ impl TextureSize {
         match &self {
             TextureSize::D1(_) => None,
             TextureSize::D2(x, _) => NonZeroU32::new(*x * bytes),
-            TextureSize::D3(x, ..) => NonZeroU32::new(*x * bytes),
+            TextureSize::D2Array(x, ..) | TextureSize::D3(x, ..) | TextureSize::Cube(x) =>
+                NonZeroU32::new(*x * bytes),
             TextureSize::Surface => NonZeroU32::new(bytes * config.width),
             TextureSize::ScaledSurface(x, _) =>
                 NonZeroU32::new(bytes * (config.width as f32 * x) as u32),
@@ -309,7 +739,8 @@ impl TextureSize {
             | TextureSize::D2(..)
             | TextureSize::Surface
             | TextureSize::ScaledSurface(..) => None,
-            TextureSize::D3(_, y, _) => NonZeroU32::new(*y),
+            TextureSize::D2Array(_, y, _) | TextureSize::D3(_, y, _) | TextureSize::Cube(y) =>
+                NonZeroU32::new(*y),
         }
     }
 }
@@ -326,6 +757,19 @@ pub struct Srgb<T>(T);
 
 pub struct Bgra<T>(T);
 
+/// Half precision floats, the data is the raw bits of each f16
+///
+/// Writing these from the cpu needs the floats to be converted to half floats first.
+/// They're mostly used as HDR render targets that are never written from the cpu.
+pub struct Half<T>(T);
+
+/// Three unsigned floats packed into a u32, 11 bits for red and green and 10 bits for blue
+///
+/// A smaller HDR render target than [`Half<[u16; 4]>`](Half) when alpha isn't needed.
+pub struct Rg11b10Float;
+/// Three 10 bit normalized channels and a 2 bit alpha packed into a u32
+pub struct Rgb10a2Unorm;
+
 macro_rules! formats {
     ($($kind: ty, $format: ident),*) => {
         $(
@@ -365,6 +809,7 @@ formats! {
     [i16; 4], Rgba16Sint,
     [u32; 4], Rgba32Uint,
     [i32; 4], Rgba32Sint,
+    [f32; 2], Rg32Float,
     [f32; 4], Rgba32Float
 }
 
@@ -390,5 +835,90 @@ formats! {
     Depth<i16>, i16, Depth16Unorm,
     Depth<Norm<u16>>, u16, Depth16Unorm,
     Depth<Norm<i16>>, i16, Depth16Unorm,
-    Depth<f32>, f32, Depth32Float
+    Depth<f32>, f32, Depth32Float,
+    Half<u16>, u16, R16Float,
+    Half<[u16; 2]>, [u16; 2], Rg16Float,
+    Half<[u16; 4]>, [u16; 4], Rgba16Float,
+    Rg11b10Float, u32, Rg11b10Float,
+    Rgb10a2Unorm, u32, Rgb10a2Unorm
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{CompositeAlphaMode, PresentMode};
+
+    use super::*;
+
+    fn config() -> SurfaceConfiguration {
+        SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: TextureFormat::Bgra8UnormSrgb,
+            width: 800,
+            height: 600,
+            present_mode: PresentMode::Fifo,
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        }
+    }
+
+    #[test]
+    fn d2_array_is_layered_2d() {
+        let size = TextureSize::D2Array(4, 8, 6);
+
+        assert_eq!(size.get_dimension(), TextureDimension::D2);
+        assert_eq!(size.get_size(&config()), Extent3d {
+            width: 4,
+            height: 8,
+            depth_or_array_layers: 6,
+        });
+        assert_eq!(size.get_bytes_per_row(4, &config()), NonZeroU32::new(16));
+        assert_eq!(size.get_rows_per_image(), NonZeroU32::new(8));
+    }
+
+    #[test]
+    fn d3_is_3d() {
+        let size = TextureSize::D3(4, 8, 6);
+
+        assert_eq!(size.get_dimension(), TextureDimension::D3);
+        assert_eq!(size.get_size(&config()).depth_or_array_layers, 6);
+        assert_eq!(size.get_rows_per_image(), NonZeroU32::new(8));
+    }
+
+    #[test]
+    fn d2_array_layers_use_array_limit() {
+        let limits = Limits::default();
+        let size = TextureSize::D2Array(4, 4, limits.max_texture_array_layers);
+
+        size.validate_limits(None, size.get_size(&config()), &limits);
+    }
+
+    #[test]
+    #[should_panic(expected = "depth or array length")]
+    fn too_many_d2_array_layers_panics() {
+        let limits = Limits::default();
+        let size = TextureSize::D2Array(4, 4, limits.max_texture_array_layers + 1);
+
+        size.validate_limits(None, size.get_size(&config()), &limits);
+    }
+
+    #[test]
+    fn rows_are_padded_to_256_bytes() {
+        assert_eq!(padded_row_size(12), 256);
+        assert_eq!(padded_row_size(256), 256);
+        assert_eq!(padded_row_size(260), 512);
+    }
+
+    #[test]
+    fn row_padding_is_stripped() {
+        // Two rows of three rgba8 texels
+        let mut padded = vec![0xFF; 512];
+        padded[.. 12].copy_from_slice(&[1; 12]);
+        padded[256 .. 268].copy_from_slice(&[2; 12]);
+
+        let mut rows = [0; 24];
+        strip_row_padding(&padded, &mut rows, 12, 256);
+
+        assert_eq!(rows[.. 12], [1; 12]);
+        assert_eq!(rows[12 ..], [2; 12]);
+    }
 }